@@ -2,7 +2,6 @@ use std::env;
 use std::io::{self, Write};
 use std::process;
 
-use md5;
 use tokio::runtime::Runtime;
 
 use modio::error::Error;
@@ -0,0 +1,357 @@
+//! Streaming zip writer used by `AddFileOptions::with_directory`.
+//!
+//! The archive is produced chunk by chunk while the files are read, so no temporary file is
+//! needed. Entries are sorted by path and use a fixed timestamp so the same directory always
+//! results in the same archive and hash. Sizes and CRCs are written in data descriptors after
+//! the compressed data. Zip64 is not supported, archives are limited to 4 GiB.
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use bytes::Bytes;
+use crc32fast::Hasher;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR: u32 = 0x0807_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+
+const VERSION: u16 = 20;
+/// Sizes and CRC in data descriptor, UTF-8 names.
+const FLAGS: u16 = 0x0008 | 0x0800;
+const DEFLATE: u16 = 8;
+/// 1980-01-01 00:00:00 in MS-DOS format.
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Include and exclude globs matched against the `/` separated path relative to the directory.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Globs {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl Globs {
+    fn build(patterns: &[String]) -> io::Result<Option<GlobSet>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern).map_err(io::Error::other)?);
+        }
+        builder.build().map(Some).map_err(io::Error::other)
+    }
+}
+
+struct Current {
+    name: String,
+    file: fs::File,
+    encoder: DeflateEncoder<Vec<u8>>,
+    hasher: Hasher,
+    size: u64,
+    compressed: u64,
+    offset: u64,
+}
+
+/// Iterator over the chunks of a zip archive of a directory.
+pub(crate) struct ZipDirectory {
+    entries: VecDeque<(String, PathBuf)>,
+    max_size: u64,
+    current: Option<Current>,
+    central: Vec<u8>,
+    count: usize,
+    offset: u64,
+    done: bool,
+}
+
+impl ZipDirectory {
+    /// Zip the files returned by [`list`]. Fails once the archive exceeds `max_size` bytes.
+    pub fn new(entries: Vec<(String, PathBuf)>, max_size: u64) -> ZipDirectory {
+        ZipDirectory {
+            entries: entries.into(),
+            max_size,
+            current: None,
+            central: Vec::new(),
+            count: 0,
+            offset: 0,
+            done: false,
+        }
+    }
+
+    fn next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+        loop {
+            if let Some(mut current) = self.current.take() {
+                let mut buf = vec![0; CHUNK_SIZE];
+                let n = current.file.read(&mut buf)?;
+                if n > 0 {
+                    current.hasher.update(&buf[..n]);
+                    current.size += n as u64;
+                    current.encoder.write_all(&buf[..n])?;
+                    let out = std::mem::take(current.encoder.get_mut());
+                    current.compressed += out.len() as u64;
+                    self.current = Some(current);
+                    if out.is_empty() {
+                        continue;
+                    }
+                    return self.emit(out).map(Some);
+                }
+                let mut out = current.encoder.finish()?;
+                current.compressed += out.len() as u64;
+                let crc = current.hasher.finalize();
+                let size = check_size(current.size)?;
+                let compressed = check_size(current.compressed)?;
+                let offset = check_size(current.offset)?;
+
+                put_u32(&mut out, DATA_DESCRIPTOR);
+                put_u32(&mut out, crc);
+                put_u32(&mut out, compressed);
+                put_u32(&mut out, size);
+
+                let c = &mut self.central;
+                put_u32(c, CENTRAL_HEADER);
+                put_u16(c, VERSION);
+                put_u16(c, VERSION);
+                put_u16(c, FLAGS);
+                put_u16(c, DEFLATE);
+                put_u16(c, DOS_TIME);
+                put_u16(c, DOS_DATE);
+                put_u32(c, crc);
+                put_u32(c, compressed);
+                put_u32(c, size);
+                put_u16(c, check_u16(current.name.len(), "filename")?);
+                put_u16(c, 0); // extra field length
+                put_u16(c, 0); // comment length
+                put_u16(c, 0); // disk number
+                put_u16(c, 0); // internal attributes
+                put_u32(c, 0); // external attributes
+                put_u32(c, offset);
+                c.extend_from_slice(current.name.as_bytes());
+                self.count += 1;
+
+                return self.emit(out).map(Some);
+            }
+
+            if let Some((name, path)) = self.entries.pop_front() {
+                let name_len = check_u16(name.len(), "filename")?;
+                let file = fs::File::open(&path)?;
+                let mut out = Vec::with_capacity(30 + name.len());
+                put_u32(&mut out, LOCAL_HEADER);
+                put_u16(&mut out, VERSION);
+                put_u16(&mut out, FLAGS);
+                put_u16(&mut out, DEFLATE);
+                put_u16(&mut out, DOS_TIME);
+                put_u16(&mut out, DOS_DATE);
+                put_u32(&mut out, 0); // crc, see data descriptor
+                put_u32(&mut out, 0); // compressed size
+                put_u32(&mut out, 0); // uncompressed size
+                put_u16(&mut out, name_len);
+                put_u16(&mut out, 0); // extra field length
+                out.extend_from_slice(name.as_bytes());
+
+                self.current = Some(Current {
+                    name,
+                    file,
+                    encoder: DeflateEncoder::new(Vec::new(), Compression::default()),
+                    hasher: Hasher::new(),
+                    size: 0,
+                    compressed: 0,
+                    offset: self.offset,
+                });
+                return self.emit(out).map(Some);
+            }
+
+            if self.done {
+                return Ok(None);
+            }
+            self.done = true;
+
+            let mut out = std::mem::take(&mut self.central);
+            let size = check_size(out.len() as u64)?;
+            let offset = check_size(self.offset)?;
+            let count = check_u16(self.count, "number of files")?;
+            put_u32(&mut out, END_OF_CENTRAL_DIR);
+            put_u16(&mut out, 0); // disk number
+            put_u16(&mut out, 0); // disk with central directory
+            put_u16(&mut out, count);
+            put_u16(&mut out, count);
+            put_u32(&mut out, size);
+            put_u32(&mut out, offset);
+            put_u16(&mut out, 0); // comment length
+            return self.emit(out).map(Some);
+        }
+    }
+
+    fn emit(&mut self, out: Vec<u8>) -> io::Result<Bytes> {
+        self.offset += out.len() as u64;
+        check_size(self.offset)?;
+        if self.offset > self.max_size {
+            let msg = format!("the archive is larger than {} bytes", self.max_size);
+            return Err(io::Error::other(msg));
+        }
+        Ok(Bytes::from(out))
+    }
+}
+
+impl Iterator for ZipDirectory {
+    type Item = io::Result<Bytes>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done && self.current.is_none() && self.entries.is_empty() {
+            return None;
+        }
+        match self.next_chunk() {
+            Ok(Some(chunk)) => Some(Ok(chunk)),
+            Ok(None) => None,
+            Err(e) => {
+                self.done = true;
+                self.current = None;
+                self.entries.clear();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Return the sorted files below `root` matching the globs.
+pub(crate) fn list(root: &Path, globs: &Globs) -> io::Result<Vec<(String, PathBuf)>> {
+    let include = Globs::build(&globs.include)?;
+    let exclude = Globs::build(&globs.exclude)?;
+    let mut entries = Vec::new();
+    walk(root, String::new(), &mut entries)?;
+    entries.retain(|(name, _)| {
+        include.as_ref().is_none_or(|set| set.is_match(name))
+            && !exclude.as_ref().is_some_and(|set| set.is_match(name))
+    });
+    entries.sort();
+    if entries.len() > usize::from(u16::MAX) {
+        return Err(io::Error::other("too many files for a zip archive"));
+    }
+    Ok(entries)
+}
+
+/// Collect the regular files below `dir`. Symbolic links are skipped.
+fn walk(dir: &Path, prefix: String, entries: &mut Vec<(String, PathBuf)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = match entry.file_name().into_string() {
+            Ok(name) => format!("{}{}", prefix, name),
+            Err(name) => {
+                let msg = format!("non UTF-8 filename: {:?}", name);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+        };
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&entry.path(), format!("{}/", name), entries)?;
+        } else if file_type.is_file() {
+            entries.push((name, entry.path()));
+        }
+    }
+    Ok(())
+}
+
+fn check_size(size: u64) -> io::Result<u32> {
+    if size > u64::from(u32::MAX) {
+        Err(io::Error::other("zip archive exceeds 4 GiB"))
+    } else {
+        Ok(size as u32)
+    }
+}
+
+fn check_u16(value: usize, what: &str) -> io::Result<u16> {
+    u16::try_from(value)
+        .map_err(|_| io::Error::other(format!("{} too large for a zip archive", what)))
+}
+
+fn put_u16(buf: &mut Vec<u8>, v: u16) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tempdir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("modio-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("textures/.git")).unwrap();
+        fs::write(dir.join("mod.lua"), b"print('hello')").unwrap();
+        fs::write(dir.join("textures/a.png"), vec![7; 200_000]).unwrap();
+        fs::write(dir.join("textures/.git/HEAD"), b"ref").unwrap();
+        dir
+    }
+
+    fn archive(dir: &Path, globs: Globs) -> Vec<u8> {
+        let mut out = Vec::new();
+        for chunk in ZipDirectory::new(list(dir, &globs).unwrap(), u64::MAX) {
+            out.extend_from_slice(&chunk.unwrap());
+        }
+        out
+    }
+
+    #[test]
+    fn deterministic() {
+        let dir = tempdir("archive");
+        let globs = Globs {
+            include: vec![],
+            exclude: vec!["**/.git/**".to_string()],
+        };
+        let first = archive(&dir, globs.clone());
+        let second = archive(&dir, globs);
+        assert_eq!(first, second);
+
+        // The end of central directory record contains the number of entries.
+        let eocd = &first[first.len() - 22..];
+        assert_eq!(&eocd[..4], &END_OF_CENTRAL_DIR.to_le_bytes());
+        assert_eq!(u16::from_le_bytes([eocd[10], eocd[11]]), 2);
+
+        #[cfg(feature = "install")]
+        {
+            use std::io::Cursor;
+
+            let mut zip = zip::ZipArchive::new(Cursor::new(first)).unwrap();
+            assert_eq!(zip.len(), 2);
+            assert_eq!(zip.by_index(0).unwrap().name(), "mod.lua");
+            let mut png = Vec::new();
+            zip.by_name("textures/a.png")
+                .unwrap()
+                .read_to_end(&mut png)
+                .unwrap();
+            assert_eq!(png, vec![7; 200_000]);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn limits() {
+        assert_eq!(check_size(u64::from(u32::MAX)).unwrap(), u32::MAX);
+        assert!(check_size(u64::from(u32::MAX) + 1).is_err());
+        assert_eq!(check_u16(65535, "filename").unwrap(), u16::MAX);
+        assert!(check_u16(65536, "filename").is_err());
+
+        // Overlong names fail instead of writing a truncated length.
+        let entries = vec![("a".repeat(70_000), PathBuf::from("missing"))];
+        let mut zip = ZipDirectory::new(entries, u64::MAX);
+        assert!(zip.next().unwrap().is_err());
+        assert!(zip.next().is_none());
+
+        // The archive stops once it exceeds the maximum size.
+        let dir = tempdir("archive-limits");
+        let zip = ZipDirectory::new(list(&dir, &Globs::default()).unwrap(), 100);
+        assert!(zip.collect::<io::Result<Vec<_>>>().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
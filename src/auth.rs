@@ -18,8 +18,8 @@ pub enum Credentials {
 impl fmt::Display for Credentials {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Credentials::ApiKey(key) => f.write_str(&key),
-            Credentials::Token(token) => f.write_str(&token),
+            Credentials::ApiKey(key) => f.write_str(key),
+            Credentials::Token(token) => f.write_str(token),
         }
     }
 }
@@ -0,0 +1,80 @@
+//! Run blocking file system work off the reactor.
+//!
+//! The work is moved to a new thread so it doesn't depend on the executor the futures are
+//! spawned on.
+use std::io;
+use std::thread;
+
+#[cfg(feature = "directory")]
+use futures::sync::mpsc;
+use futures::sync::oneshot;
+use futures::Future as StdFuture;
+#[cfg(feature = "directory")]
+use futures::{Sink, Stream as StdStream};
+
+use crate::error::{self, Error};
+use crate::Future;
+
+/// Run `f` on a new thread and resolve to its result.
+pub(crate) fn run<F, T>(f: F) -> Future<T>
+where
+    F: FnOnce() -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(f());
+    });
+    Box::new(rx.map_err(|_| aborted()).and_then(|ret| ret))
+}
+
+/// Drive `iter` on a new thread and yield its items.
+///
+/// The thread reads at most one item ahead and stops once the stream is dropped.
+#[cfg(feature = "directory")]
+pub(crate) fn iter<I, T>(iter: I) -> impl StdStream<Item = T, Error = io::Error> + Send + Sync
+where
+    I: Iterator<Item = io::Result<T>> + Send + 'static,
+    T: Send + Sync + 'static,
+{
+    let (tx, rx) = mpsc::channel(0);
+    thread::spawn(move || {
+        let mut tx = tx.wait();
+        for item in iter {
+            if tx.send(item).is_err() {
+                return;
+            }
+        }
+        let _ = tx.close();
+    });
+    rx.then(|item| match item {
+        Ok(item) => item,
+        Err(()) => Err(io::Error::other("blocking task aborted")),
+    })
+}
+
+fn aborted() -> Error {
+    error::from(io::Error::other("blocking task aborted"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run() {
+        assert_eq!(super::run(|| Ok(1 + 1)).wait().unwrap(), 2);
+        assert!(super::run(|| Err::<(), _>(aborted())).wait().is_err());
+    }
+
+    #[cfg(feature = "directory")]
+    #[test]
+    fn iter() {
+        let items = vec![Ok(1), Ok(2), Err(io::Error::other("failed"))];
+        let mut stream = super::iter(items.into_iter()).wait();
+        assert_eq!(stream.next().unwrap().unwrap(), 1);
+        assert_eq!(stream.next().unwrap().unwrap(), 2);
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
+}
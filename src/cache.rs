@@ -0,0 +1,441 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use bytes::Bytes;
+use futures::sync::{mpsc, oneshot};
+use futures::{Async, AsyncSink, Future as StdFuture, Poll, Sink, Stream};
+use log::{debug, warn};
+
+use crate::blocking;
+use crate::error::Error;
+use crate::Future;
+
+const PARTIAL: &str = "part";
+/// The number of chunks buffered for the writer thread.
+const BUFFERED_CHUNKS: usize = 4;
+
+/// On-disk cache of downloaded modfiles shared by all clones of a `Modio` client.
+///
+/// Entries are stored as `<md5>-<file id>` in the cache directory. The modification time of an
+/// entry is updated on every hit and used to evict the least recently used entries once the
+/// total size exceeds the limit. Reading, verifying and writing entries is done on separate
+/// threads.
+#[derive(Clone, Debug)]
+pub(crate) struct Cache {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    dir: PathBuf,
+    max_size: u64,
+    counter: AtomicUsize,
+    evict: Mutex<()>,
+}
+
+/// Identifies a modfile by its id and md5 hash.
+#[derive(Clone, Debug)]
+pub(crate) struct CacheKey {
+    file_id: u32,
+    md5: String,
+}
+
+impl CacheKey {
+    /// Returns `None` if `md5` is not a valid md5 hex digest.
+    pub fn new(file_id: u32, md5: &str) -> Option<CacheKey> {
+        if md5.len() == 32 && md5.bytes().all(|b| b.is_ascii_hexdigit()) {
+            Some(CacheKey {
+                file_id,
+                md5: md5.to_ascii_lowercase(),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn filename(&self) -> String {
+        format!("{}-{}", self.md5, self.file_id)
+    }
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, max_size: u64) -> io::Result<Cache> {
+        fs::create_dir_all(&dir)?;
+        Ok(Cache {
+            inner: Arc::new(Inner {
+                dir,
+                max_size,
+                counter: AtomicUsize::new(0),
+                evict: Mutex::new(()),
+            }),
+        })
+    }
+
+    /// Resolves to the path of a cached modfile after verifying its md5 hash.
+    ///
+    /// Entries with a mismatching hash are removed.
+    pub fn get(&self, key: &CacheKey) -> Future<Option<PathBuf>> {
+        let cache = self.clone();
+        let key = key.clone();
+        blocking::run(move || Ok(cache.lookup(&key)))
+    }
+
+    fn lookup(&self, key: &CacheKey) -> Option<PathBuf> {
+        let path = self.inner.dir.join(key.filename());
+        let digest = match md5_file(&path) {
+            Ok(digest) => digest,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("cache: failed to read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        if digest != key.md5 {
+            warn!("cache: removing corrupted entry {}", path.display());
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        if let Err(e) = touch(&path) {
+            debug!("cache: failed to update {}: {}", path.display(), e);
+        }
+        debug!("cache: hit {}", path.display());
+        Some(path)
+    }
+
+    /// Returns a sink for the chunks of a new entry. The chunks are written by a separate thread
+    /// which adds the entry once the sink is finished. Only a few chunks are buffered, a slow
+    /// disk slows down the download instead of filling the memory.
+    pub fn writer(&self, key: CacheKey) -> CacheSink {
+        let (tx, rx) = mpsc::channel::<Option<Bytes>>(BUFFERED_CHUNKS);
+        let (done, finished) = oneshot::channel();
+        let cache = self.clone();
+        thread::spawn(move || {
+            let mut writer = match cache.create_writer(key) {
+                Some(writer) => writer,
+                None => return,
+            };
+            for chunk in rx.wait() {
+                match chunk {
+                    Ok(Some(chunk)) => writer.write(&chunk),
+                    Ok(None) => {
+                        writer.finish();
+                        break;
+                    }
+                    Err(()) => break,
+                }
+            }
+            let _ = done.send(());
+        });
+        CacheSink {
+            tx,
+            finished: Some(finished),
+        }
+    }
+
+    /// Returns a writer for a new entry or `None` if the temporary file can't be created.
+    fn create_writer(&self, key: CacheKey) -> Option<CacheWriter> {
+        let tmp = self.inner.dir.join(format!(
+            "{}.{}.{}.{}",
+            key.filename(),
+            std::process::id(),
+            self.inner.counter.fetch_add(1, Ordering::Relaxed),
+            PARTIAL,
+        ));
+        match fs::File::create(&tmp) {
+            Ok(file) => Some(CacheWriter {
+                cache: self.clone(),
+                key,
+                tmp,
+                file: Some(file),
+                ctx: md5::Context::new(),
+            }),
+            Err(e) => {
+                warn!("cache: failed to create {}: {}", tmp.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Removes the least recently used entries until the total size fits into the limit.
+    fn evict(&self) -> io::Result<()> {
+        let _guard = self.inner.evict.lock().expect("cache evict lock");
+        let mut entries = Vec::new();
+        let mut total = 0;
+        for entry in fs::read_dir(&self.inner.dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == PARTIAL) {
+                continue;
+            }
+            let meta = entry.metadata()?;
+            if !meta.is_file() {
+                continue;
+            }
+            total += meta.len();
+            entries.push((meta.modified()?, meta.len(), path));
+        }
+        entries.sort();
+        for (_, len, path) in entries {
+            if total <= self.inner.max_size {
+                break;
+            }
+            debug!("cache: evict {}", path.display());
+            fs::remove_file(&path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+/// Sends the chunks of a download to the thread writing the cache entry.
+pub(crate) struct CacheSink {
+    tx: mpsc::Sender<Option<Bytes>>,
+    finished: Option<oneshot::Receiver<()>>,
+}
+
+/// Writes a downloaded modfile into the cache. The entry is only added if the md5 hash of the
+/// written data matches, otherwise the temporary file is removed.
+struct CacheWriter {
+    cache: Cache,
+    key: CacheKey,
+    tmp: PathBuf,
+    file: Option<fs::File>,
+    ctx: md5::Context,
+}
+
+impl CacheWriter {
+    fn write(&mut self, data: &[u8]) {
+        if let Some(mut file) = self.file.take() {
+            match file.write_all(data) {
+                Ok(()) => {
+                    self.ctx.consume(data);
+                    self.file = Some(file);
+                }
+                Err(e) => warn!("cache: failed to write {}: {}", self.tmp.display(), e),
+            }
+        }
+    }
+
+    fn finish(mut self) {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => return,
+        };
+        drop(file);
+
+        let ctx = std::mem::replace(&mut self.ctx, md5::Context::new());
+        let digest = format!("{:x}", ctx.compute());
+        if digest != self.key.md5 {
+            warn!(
+                "cache: md5 mismatch for file {}: expected {}, got {}",
+                self.key.file_id, self.key.md5, digest
+            );
+            return;
+        }
+        let path = self.cache.inner.dir.join(self.key.filename());
+        if let Err(e) = fs::rename(&self.tmp, &path) {
+            warn!("cache: failed to add {}: {}", path.display(), e);
+            return;
+        }
+        if let Err(e) = self.cache.evict() {
+            warn!("cache: eviction failed: {}", e);
+        }
+    }
+}
+
+impl Drop for CacheWriter {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.tmp);
+    }
+}
+
+/// Passes the chunks of a download through while writing them into the cache.
+///
+/// A chunk is passed on once the writer thread accepted its copy. The stream ends after the
+/// cache entry was added. Failing to write the cache never fails the download.
+pub(crate) struct Tee<S> {
+    inner: S,
+    sink: Option<CacheSink>,
+    /// A chunk or the end of the stream not yet accepted by the writer thread.
+    pending: Option<Option<Bytes>>,
+    finished: Option<oneshot::Receiver<()>>,
+}
+
+impl<S> Tee<S> {
+    pub fn new(inner: S, sink: CacheSink) -> Tee<S> {
+        Tee {
+            inner,
+            sink: Some(sink),
+            pending: None,
+            finished: None,
+        }
+    }
+}
+
+impl<S> Stream for Tee<S>
+where
+    S: Stream<Item = Bytes, Error = Error>,
+{
+    type Item = Bytes;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Bytes>, Error> {
+        loop {
+            if let Some(finished) = self.finished.as_mut() {
+                // A closed channel means the writer thread stopped early, the download is done
+                // anyway.
+                return match finished.poll() {
+                    Ok(Async::NotReady) => Ok(Async::NotReady),
+                    _ => {
+                        self.finished = None;
+                        Ok(Async::Ready(None))
+                    }
+                };
+            }
+            let chunk = match self.pending.take() {
+                Some(chunk) => chunk,
+                None => match self.inner.poll() {
+                    Ok(Async::Ready(chunk)) => chunk,
+                    Ok(Async::NotReady) => return Ok(Async::NotReady),
+                    Err(e) => {
+                        self.sink = None;
+                        return Err(e);
+                    }
+                },
+            };
+            if let Some(sink) = self.sink.as_mut() {
+                match sink.tx.start_send(chunk.clone()) {
+                    Ok(AsyncSink::Ready) => {}
+                    Ok(AsyncSink::NotReady(_)) => {
+                        self.pending = Some(chunk);
+                        return Ok(Async::NotReady);
+                    }
+                    Err(_) => self.sink = None,
+                }
+            }
+            match chunk {
+                Some(chunk) => return Ok(Async::Ready(Some(chunk))),
+                None => match self.sink.take() {
+                    Some(mut sink) => self.finished = sink.finished.take(),
+                    None => return Ok(Async::Ready(None)),
+                },
+            }
+        }
+    }
+}
+
+fn md5_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut ctx = md5::Context::new();
+    io::copy(&mut file, &mut ctx)?;
+    Ok(format!("{:x}", ctx.compute()))
+}
+
+fn touch(path: &Path) -> io::Result<()> {
+    fs::OpenOptions::new()
+        .write(true)
+        .open(path)?
+        .set_modified(SystemTime::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{stream, Future};
+
+    use super::*;
+
+    fn tempdir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("modio-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn add(cache: &Cache, file_id: u32, data: &'static [u8]) -> CacheKey {
+        let md5 = format!("{:x}", md5::compute(data));
+        let key = CacheKey::new(file_id, &md5).unwrap();
+        let chunks = stream::iter_ok(vec![Bytes::from_static(data)]);
+        Tee::new(chunks, cache.writer(key.clone()))
+            .collect()
+            .wait()
+            .unwrap();
+        key
+    }
+
+    fn get(cache: &Cache, key: &CacheKey) -> Option<PathBuf> {
+        cache.get(key).wait().unwrap()
+    }
+
+    #[test]
+    fn keys() {
+        assert!(CacheKey::new(1, "d41d8cd98f00b204e9800998ecf8427e").is_some());
+        assert!(CacheKey::new(1, "../../etc/passwd").is_none());
+        assert!(CacheKey::new(1, "").is_none());
+    }
+
+    #[test]
+    fn hit_verify_and_evict() {
+        let dir = tempdir("cache");
+        let cache = Cache::new(dir.clone(), 10).unwrap();
+
+        let first = add(&cache, 1, b"hello");
+        let path = get(&cache, &first).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"hello");
+
+        // A corrupted entry is removed on read.
+        fs::write(&path, b"jello").unwrap();
+        assert!(get(&cache, &first).is_none());
+        assert!(!path.exists());
+
+        let first = add(&cache, 1, b"hello");
+        let path = get(&cache, &first).unwrap();
+        let past = SystemTime::now() - Duration::from_secs(60);
+        fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+
+        // Adding a second entry exceeds the limit and evicts the least recently used one.
+        let second = add(&cache, 2, b"world!");
+        assert!(get(&cache, &first).is_none());
+        assert!(get(&cache, &second).is_some());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn backpressure() {
+        let (tx, rx) = mpsc::channel(0);
+        let (_done, finished) = oneshot::channel();
+        let sink = CacheSink {
+            tx,
+            finished: Some(finished),
+        };
+        let chunks = stream::iter_ok(vec![Bytes::from("a"), Bytes::from("b"), Bytes::from("c")]);
+        let mut tee = Tee::new(chunks, sink);
+
+        futures::future::lazy(move || {
+            assert_eq!(tee.poll().unwrap(), Async::Ready(Some(Bytes::from("a"))));
+            // The writer didn't take the first chunk yet, the second one waits.
+            assert_eq!(tee.poll().unwrap(), Async::NotReady);
+            assert_eq!(tee.poll().unwrap(), Async::NotReady);
+
+            // A writer that stopped doesn't fail the download.
+            drop(rx);
+            assert_eq!(tee.poll().unwrap(), Async::Ready(Some(Bytes::from("b"))));
+            assert_eq!(tee.poll().unwrap(), Async::Ready(Some(Bytes::from("c"))));
+            assert_eq!(tee.poll().unwrap(), Async::Ready(None));
+            Ok::<_, ()>(())
+        })
+        .wait()
+        .unwrap();
+    }
+}
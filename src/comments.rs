@@ -24,7 +24,7 @@ impl Comments {
     /// List all comments.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<Comment>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Comments>>) -> Future<List<Comment>> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -36,7 +36,7 @@ impl Comments {
     /// Provides a stream over all comments of the mod.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<Comment> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Comments>>) -> Stream<Comment> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -45,6 +45,13 @@ impl Comments {
         self.modio.stream(&uri.join("?"))
     }
 
+    /// Provides a stream over all comments of the mod that pass any of the alternatives.
+    ///
+    /// See [OR-queries](../filter/any/index.html).
+    pub fn iter_any(&self, filter: &AnyOf<impl Scope<endpoint::Comments>>) -> Stream<Comment> {
+        self.modio.stream_any(&self.path(""), filter)
+    }
+
     /// Return comment by id.
     pub fn get(&self, id: u32) -> Future<Comment> {
         self.modio.get(&self.path(&format!("/{}", id)))
@@ -102,8 +109,23 @@ pub mod filters {
     #[doc(inline)]
     pub use crate::filter::prelude::SubmittedBy;
 
-    filter!(ReplyId, REPLY_ID, "reply_id", Eq, NotEq, In, Cmp);
-    filter!(ThreadPosition, THREAD_POSITION, "thread_position", Eq, NotEq, In, Like);
-    filter!(Karma, KARMA, "karma", Eq, NotEq, In, Cmp);
-    filter!(Content, CONTENT, "content", Eq, NotEq, Like);
+    type Endpoint = crate::filter::endpoint::Comments;
+
+    filter!(ReplyId, REPLY_ID, "reply_id", u32: Eq, NotEq, In, Cmp);
+    filter!(ThreadPosition, THREAD_POSITION, "thread_position", String: Eq, NotEq, In, Like);
+    filter!(Karma, KARMA, "karma", u32: Eq, NotEq, In, Cmp);
+    filter!(Content, CONTENT, "content", String: Eq, NotEq, Like);
+
+    /// The comment fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+    pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+        Fulltext::FIELD,
+        Id::FIELD,
+        ModId::FIELD,
+        DateAdded::FIELD,
+        SubmittedBy::FIELD,
+        ReplyId::FIELD,
+        ThreadPosition::FIELD,
+        Karma::FIELD,
+        Content::FIELD,
+    ]);
 }
@@ -0,0 +1,219 @@
+//! Dependency resolution
+//!
+//! [`ModRef::resolve_dependencies`](../mods/struct.ModRef.html#method.resolve_dependencies)
+//! walks the dependencies of a mod transitively and returns an [`InstallPlan`] with the
+//! dependencies ordered before the mods requiring them.
+//!
+//! # Example
+//! ```no_run
+//! use modio::{Credentials, Error, Modio};
+//! use tokio::runtime::Runtime;
+//!
+//! fn main() -> Result<(), Error> {
+//!     let mut rt = Runtime::new().expect("new rt");
+//!     let modio = Modio::new(
+//!         Credentials::ApiKey(String::from("user-or-game-api-key")),
+//!     )?;
+//!
+//!     let plan = rt.block_on(modio.mod_(5, 19).resolve_dependencies())?;
+//!     for (m, action) in plan.mods().iter().zip(plan.actions()) {
+//!         let out = std::fs::File::create(format!("{}.zip", m.name_id)).expect("new file");
+//!         rt.block_on(modio.download(action, out))?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`InstallPlan`]: struct.InstallPlan.html
+use std::collections::{BTreeMap, BTreeSet};
+
+use futures::future::{self, Loop};
+use futures::{Future as StdFuture, Stream as StdStream};
+use reqwest::StatusCode;
+
+use crate::error::{self, ErrorKind, Result};
+use crate::mods::{Mod, Status, Visibility};
+use crate::{DownloadAction, Future, Modio};
+
+/// Mods in installation order. Every mod is preceded by its dependencies and the mod the plan
+/// was resolved for is the last one.
+#[derive(Debug)]
+pub struct InstallPlan {
+    mods: Vec<Mod>,
+}
+
+impl InstallPlan {
+    pub fn mods(&self) -> &[Mod] {
+        &self.mods
+    }
+
+    pub fn into_mods(self) -> Vec<Mod> {
+        self.mods
+    }
+
+    /// Return the download actions for the primary files of the mods in installation order.
+    ///
+    /// The actions download the files the plan was resolved with, even if a mod gets a new
+    /// primary file in the meantime.
+    pub fn actions(&self) -> Vec<DownloadAction> {
+        self.mods
+            .iter()
+            .filter_map(|m| m.modfile.clone())
+            .map(DownloadAction::from)
+            .collect()
+    }
+}
+
+struct Walk {
+    frontier: Vec<(u32, Option<u32>)>,
+    seen: BTreeSet<u32>,
+    mods: BTreeMap<u32, Mod>,
+    graph: BTreeMap<u32, Vec<u32>>,
+}
+
+pub(crate) fn resolve(modio: Modio, game_id: u32, mod_id: u32) -> Future<InstallPlan> {
+    let walk = Walk {
+        frontier: vec![(mod_id, None)],
+        seen: vec![mod_id].into_iter().collect(),
+        mods: BTreeMap::new(),
+        graph: BTreeMap::new(),
+    };
+
+    let task = future::loop_fn(walk, move |mut walk| {
+        let requests = walk
+            .frontier
+            .drain(..)
+            .map(|(id, parent)| fetch(&modio, game_id, id, parent))
+            .collect::<Vec<_>>();
+
+        future::join_all(requests).and_then(move |results| {
+            for (parent, m, deps) in results {
+                match parent {
+                    Some(parent) => {
+                        let available = matches!(m.status, Status::Accepted)
+                            && matches!(m.visible, Visibility::Public)
+                            && m.modfile.is_some();
+                        if !available {
+                            return Err(error::dependency_unavailable(m.id, parent));
+                        }
+                    }
+                    None if m.modfile.is_none() => {
+                        return Err(error::download_no_primary(game_id, m.id));
+                    }
+                    None => {}
+                }
+                for dep in &deps {
+                    if walk.seen.insert(*dep) {
+                        walk.frontier.push((*dep, Some(m.id)));
+                    }
+                }
+                walk.graph.insert(m.id, deps);
+                walk.mods.insert(m.id, m);
+            }
+            if walk.frontier.is_empty() {
+                Ok(Loop::Break(walk))
+            } else {
+                Ok(Loop::Continue(walk))
+            }
+        })
+    });
+
+    Box::new(task.and_then(move |mut walk| {
+        let order = sort(mod_id, &walk.graph)?;
+        let mods = order
+            .into_iter()
+            .filter_map(|id| walk.mods.remove(&id))
+            .collect();
+        Ok(InstallPlan { mods })
+    }))
+}
+
+/// Fetch a mod and the ids of its direct dependencies.
+fn fetch(
+    modio: &Modio,
+    game_id: u32,
+    mod_id: u32,
+    parent: Option<u32>,
+) -> impl StdFuture<Item = (Option<u32>, Mod, Vec<u32>), Error = crate::Error> {
+    let mod_ref = modio.mod_(game_id, mod_id);
+    let deps = mod_ref.dependencies().iter().map(|d| d.mod_id).collect();
+    mod_ref
+        .get()
+        .join(deps)
+        .map(move |(m, deps)| (parent, m, deps))
+        .map_err(move |e| match (e.kind(), parent) {
+            (ErrorKind::Fault { code, .. }, Some(parent)) if *code == StatusCode::NOT_FOUND => {
+                error::dependency_missing(mod_id, parent)
+            }
+            _ => e,
+        })
+}
+
+/// Order the mods reachable from `root` so that every mod follows its dependencies.
+fn sort(root: u32, graph: &BTreeMap<u32, Vec<u32>>) -> Result<Vec<u32>> {
+    fn visit(
+        id: u32,
+        graph: &BTreeMap<u32, Vec<u32>>,
+        path: &mut Vec<u32>,
+        done: &mut BTreeSet<u32>,
+        order: &mut Vec<u32>,
+    ) -> Result<()> {
+        if done.contains(&id) {
+            return Ok(());
+        }
+        if let Some(pos) = path.iter().position(|p| *p == id) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(id);
+            return Err(error::dependency_cycle(cycle));
+        }
+        path.push(id);
+        for dep in graph.get(&id).into_iter().flatten() {
+            visit(*dep, graph, path, done, order)?;
+        }
+        path.pop();
+        done.insert(id);
+        order.push(id);
+        Ok(())
+    }
+
+    let mut order = Vec::new();
+    visit(
+        root,
+        graph,
+        &mut Vec::new(),
+        &mut BTreeSet::new(),
+        &mut order,
+    )?;
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::DependencyError;
+
+    fn graph(edges: &[(u32, &[u32])]) -> BTreeMap<u32, Vec<u32>> {
+        edges
+            .iter()
+            .map(|(id, deps)| (*id, deps.to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn install_order() {
+        let g = graph(&[(1, &[2, 3]), (2, &[4]), (3, &[4]), (4, &[])]);
+        assert_eq!(sort(1, &g).unwrap(), vec![4, 2, 3, 1]);
+    }
+
+    #[test]
+    fn cycle() {
+        let g = graph(&[(1, &[2]), (2, &[3]), (3, &[2])]);
+        let err = sort(1, &g).unwrap_err();
+        match err.kind() {
+            ErrorKind::Dependency(DependencyError::Cycle { mod_ids }) => {
+                assert_eq!(mod_ids, &[2, 3, 2])
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+}
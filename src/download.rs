@@ -1,3 +1,5 @@
+use std::fmt;
+
 use url::Url;
 
 use crate::types::mods::{File, Mod};
@@ -20,25 +22,221 @@ pub enum DownloadAction {
         version: String,
         policy: ResolvePolicy,
     },
+    /// Download the file with the highest version matching a semver requirement.
+    ///
+    /// A leading `v` of `File::version` is ignored and missing minor or patch numbers are
+    /// treated as `0`. Requires the `semver` feature.
+    #[cfg(feature = "semver")]
+    VersionReq {
+        game_id: u32,
+        mod_id: u32,
+        req: semver::VersionReq,
+    },
+    /// Download a modfile. The cache and the virus policy are applied like for the other
+    /// actions.
+    FileObj(Box<File>),
     /// Url to download.
     Url(Url),
 }
 
-/// Defines the policy for `DownloadAction::Version` when multiple files are found.
+/// The file resolved for a `DownloadAction` by [`Modio::resolve`](../struct.Modio.html#method.resolve).
 #[derive(Debug)]
+pub struct Resolved {
+    /// The url to download.
+    pub url: Url,
+    /// The modfile to download, `None` for `DownloadAction::Url`.
+    pub file: Option<File>,
+    /// The files skipped because their version couldn't be parsed as semver.
+    ///
+    /// Only filled for `DownloadAction::VersionReq`.
+    pub skipped: Vec<File>,
+}
+
+impl Resolved {
+    pub(crate) fn new(url: Url, file: Option<File>) -> Resolved {
+        Resolved {
+            url,
+            file,
+            skipped: Vec::new(),
+        }
+    }
+}
+
+/// Closure type of [`ResolvePolicy::Custom`](enum.ResolvePolicy.html#variant.Custom).
+pub type SelectFile = dyn Fn(&[File]) -> Option<usize> + Send + Sync;
+
+/// Defines the policy for `DownloadAction::Version` when multiple files are found.
 pub enum ResolvePolicy {
     /// Download the latest file.
     Latest,
+    /// Download the earliest file.
+    Earliest,
+    /// Download the file with the largest `filesize`.
+    Largest,
+    /// Download the file with the smallest `filesize`.
+    Smallest,
+    /// Download the latest file whose `filename` matches the pattern or the latest file if none
+    /// matches.
+    ///
+    /// The pattern is matched against the whole filename, `*` matches any sequence of characters
+    /// and `?` matches a single character, e.g. `*-linux.zip`.
+    PreferFilename(String),
+    /// Let the closure choose the index of the file. The files are ordered by the date they were
+    /// added, latest first. Returning `None` fails like `ResolvePolicy::Fail`.
+    Custom(Box<SelectFile>),
     /// Return with `ErrorKind::Download(DownloadError::MultipleFilesFound)`.
     Fail,
 }
 
-/// Convert `Mod` to [`DownloadAction::Url`](enum.DownloadAction.html#variant.Url) or
+impl ResolvePolicy {
+    /// Create a `ResolvePolicy::Custom` from a closure.
+    pub fn custom<F>(f: F) -> ResolvePolicy
+    where
+        F: Fn(&[File]) -> Option<usize> + Send + Sync + 'static,
+    {
+        ResolvePolicy::Custom(Box::new(f))
+    }
+
+    /// Select the index of the file to download. `files` are ordered by date, latest first.
+    pub(crate) fn select(&self, files: &[File]) -> Option<usize> {
+        use ResolvePolicy::*;
+
+        if files.len() < 2 {
+            return if files.is_empty() { None } else { Some(0) };
+        }
+        match self {
+            Latest => Some(0),
+            Earliest => Some(files.len() - 1),
+            Largest => files
+                .iter()
+                .enumerate()
+                .rev()
+                .max_by_key(|(_, f)| f.filesize)
+                .map(|(i, _)| i),
+            Smallest => files
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, f)| f.filesize)
+                .map(|(i, _)| i),
+            PreferFilename(pattern) => files
+                .iter()
+                .position(|f| glob_match(pattern, &f.filename))
+                .or(Some(0)),
+            Custom(f) => f(files).filter(|i| *i < files.len()),
+            Fail => None,
+        }
+    }
+}
+
+impl fmt::Debug for ResolvePolicy {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolvePolicy::Latest => fmt.write_str("Latest"),
+            ResolvePolicy::Earliest => fmt.write_str("Earliest"),
+            ResolvePolicy::Largest => fmt.write_str("Largest"),
+            ResolvePolicy::Smallest => fmt.write_str("Smallest"),
+            ResolvePolicy::PreferFilename(p) => fmt.debug_tuple("PreferFilename").field(p).finish(),
+            ResolvePolicy::Custom(_) => fmt.write_str("Custom(..)"),
+            ResolvePolicy::Fail => fmt.write_str("Fail"),
+        }
+    }
+}
+
+/// Match `text` against a pattern with `*` and `?` wildcards.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p = pattern.chars().collect::<Vec<_>>();
+    let t = text.chars().collect::<Vec<_>>();
+    let (mut pi, mut ti) = (0, 0);
+    let mut backtrack = None;
+    while ti < t.len() {
+        match p.get(pi) {
+            Some('*') => {
+                backtrack = Some((pi, ti));
+                pi += 1;
+            }
+            Some(c) if *c == '?' || *c == t[ti] => {
+                pi += 1;
+                ti += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bt)) => {
+                    pi = bp + 1;
+                    ti = bt + 1;
+                    backtrack = Some((bp, bt + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+/// Closure type of [`VirusPolicy::Warn`](enum.VirusPolicy.html#variant.Warn).
+pub type WarnFile = dyn Fn(&File) + Send + Sync;
+
+/// Defines how downloads of files that are not scanned or were flagged by the virus scanner are
+/// handled.
+///
+/// The policy is only applied if the file is known before downloading, which is not the case for
+/// [`DownloadAction::Url`](enum.DownloadAction.html#variant.Url). Convert a `File` or `Mod` into
+/// a `DownloadAction` instead of using its `binary_url`.
+pub enum VirusPolicy {
+    /// Download all files.
+    Allow,
+    /// Call the closure and download the file.
+    Warn(Box<WarnFile>),
+    /// Return with `ErrorKind::Download(DownloadError::UnsafeFile)`.
+    Deny,
+}
+
+impl VirusPolicy {
+    /// Create a `VirusPolicy::Warn` from a closure.
+    pub fn warn<F>(f: F) -> VirusPolicy
+    where
+        F: Fn(&File) + Send + Sync + 'static,
+    {
+        VirusPolicy::Warn(Box::new(f))
+    }
+
+    /// Return `false` if the download of the file should be refused.
+    pub(crate) fn check(&self, file: &File) -> bool {
+        if file.is_safe() {
+            return true;
+        }
+        match self {
+            VirusPolicy::Allow => true,
+            VirusPolicy::Warn(f) => {
+                f(file);
+                true
+            }
+            VirusPolicy::Deny => false,
+        }
+    }
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for VirusPolicy {
+    fn default() -> VirusPolicy {
+        VirusPolicy::Allow
+    }
+}
+
+impl fmt::Debug for VirusPolicy {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VirusPolicy::Allow => fmt.write_str("Allow"),
+            VirusPolicy::Warn(_) => fmt.write_str("Warn(..)"),
+            VirusPolicy::Deny => fmt.write_str("Deny"),
+        }
+    }
+}
+
+/// Convert `Mod` to [`DownloadAction::FileObj`](enum.DownloadAction.html#variant.FileObj) or
 /// [`DownloadAction::Primary`](enum.DownloadAction.html#variant.Primary) if `Mod::modfile` is `None`
 impl From<Mod> for DownloadAction {
     fn from(m: Mod) -> DownloadAction {
         if let Some(file) = m.modfile {
-            DownloadAction::Url(file.download.binary_url)
+            DownloadAction::FileObj(Box::new(file))
         } else {
             DownloadAction::Primary {
                 game_id: m.game_id,
@@ -48,10 +246,21 @@ impl From<Mod> for DownloadAction {
     }
 }
 
-/// Convert `File` to [`DownloadAction::Url`](enum.DownloadAction.html#variant.Url)
+/// Convert `File` to [`DownloadAction::FileObj`](enum.DownloadAction.html#variant.FileObj)
 impl From<File> for DownloadAction {
     fn from(file: File) -> DownloadAction {
-        DownloadAction::Url(file.download.binary_url)
+        DownloadAction::FileObj(Box::new(file))
+    }
+}
+
+/// Convert `Resolved` to [`DownloadAction::FileObj`](enum.DownloadAction.html#variant.FileObj)
+/// or [`DownloadAction::Url`](enum.DownloadAction.html#variant.Url) if the file is unknown
+impl From<Resolved> for DownloadAction {
+    fn from(resolved: Resolved) -> DownloadAction {
+        match resolved.file {
+            Some(file) => DownloadAction::FileObj(Box::new(file)),
+            None => DownloadAction::Url(resolved.url),
+        }
     }
 }
 
@@ -104,3 +313,183 @@ impl From<Url> for DownloadAction {
         DownloadAction::Url(url)
     }
 }
+
+/// Parse the version of a file leniently.
+#[cfg(feature = "semver")]
+pub(crate) fn parse_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim();
+    let version = version
+        .strip_prefix('v')
+        .or_else(|| version.strip_prefix('V'))
+        .unwrap_or(version);
+    if let Ok(v) = semver::Version::parse(version) {
+        return Some(v);
+    }
+    let numbers = version.split('.').collect::<Vec<_>>();
+    if numbers.len() < 3 && numbers.iter().all(|n| n.parse::<u64>().is_ok()) {
+        let mut padded = numbers;
+        padded.resize(3, "0");
+        return semver::Version::parse(&padded.join(".")).ok();
+    }
+    None
+}
+
+/// Select the index of the highest version matching `req`. Later entries win on equal versions.
+///
+/// Returns the indices of the unparsable versions as second element.
+#[cfg(feature = "semver")]
+pub(crate) fn select_version<'a, I>(
+    versions: I,
+    req: &semver::VersionReq,
+) -> (Option<usize>, Vec<usize>)
+where
+    I: IntoIterator<Item = Option<&'a str>>,
+{
+    let mut best: Option<(usize, semver::Version)> = None;
+    let mut invalid = Vec::new();
+    for (i, version) in versions.into_iter().enumerate() {
+        let version = match version {
+            Some(version) => version,
+            None => continue,
+        };
+        let parsed = match parse_version(version) {
+            Some(parsed) => parsed,
+            None => {
+                invalid.push(i);
+                continue;
+            }
+        };
+        if !req.matches(&parsed) {
+            continue;
+        }
+        match best {
+            Some((_, ref v)) if *v > parsed => {}
+            _ => best = Some((i, parsed)),
+        }
+    }
+    (best.map(|(i, _)| i), invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(id: u32, filesize: u64, filename: &str) -> File {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "mod_id": 1, "date_added": 0, "date_scanned": 0, "virus_status": 1,
+            "virus_positive": 0, "virustotal_hash": null, "filesize": filesize,
+            "filehash": {"md5": ""}, "filename": filename, "version": "1.0", "changelog": null,
+            "metadata_blob": null,
+            "download": {"binary_url": "https://example.com/f.zip", "date_expires": 0},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_policies() {
+        // Latest first like the files requested by `Modio::download`.
+        let files = vec![
+            file(4, 20, "mod-windows.zip"),
+            file(3, 30, "mod-linux.zip"),
+            file(2, 10, "mod-windows.zip"),
+            file(1, 30, "mod-linux.zip"),
+        ];
+        let select = |policy: ResolvePolicy| policy.select(&files);
+
+        assert_eq!(select(ResolvePolicy::Latest), Some(0));
+        assert_eq!(select(ResolvePolicy::Earliest), Some(3));
+        assert_eq!(select(ResolvePolicy::Largest), Some(1));
+        assert_eq!(select(ResolvePolicy::Smallest), Some(2));
+        let pattern = |p: &str| ResolvePolicy::PreferFilename(p.to_string());
+        assert_eq!(select(pattern("*-linux.zip")), Some(1));
+        assert_eq!(select(pattern("*-macos.zip")), Some(0));
+        let custom = ResolvePolicy::custom(|files| files.iter().position(|f| f.id == 2));
+        assert_eq!(select(custom), Some(2));
+        assert_eq!(select(ResolvePolicy::custom(|_| Some(10))), None);
+        assert_eq!(select(ResolvePolicy::custom(|_| None)), None);
+        assert_eq!(select(ResolvePolicy::Fail), None);
+    }
+
+    #[test]
+    fn virus_policies() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        use crate::types::mods::{VirusPositive, VirusStatus};
+
+        let safe = file(1, 10, "mod.zip");
+        let mut unscanned = file(2, 10, "mod.zip");
+        unscanned.virus_status = VirusStatus::NotScanned;
+        let mut flagged = file(3, 10, "mod.zip");
+        flagged.virus_positive = VirusPositive::Malicious;
+
+        for policy in &[VirusPolicy::Allow, VirusPolicy::Deny] {
+            assert!(policy.check(&safe));
+        }
+        assert!(VirusPolicy::Allow.check(&unscanned));
+        assert!(VirusPolicy::Allow.check(&flagged));
+        assert!(!VirusPolicy::Deny.check(&unscanned));
+        assert!(!VirusPolicy::Deny.check(&flagged));
+
+        let warned = Arc::new(AtomicUsize::new(0));
+        let counter = warned.clone();
+        let warn = VirusPolicy::warn(move |f| {
+            counter.fetch_add(f.id as usize, Ordering::SeqCst);
+        });
+        assert!(warn.check(&safe));
+        assert!(warn.check(&unscanned));
+        assert!(warn.check(&flagged));
+        assert_eq!(warned.load(Ordering::SeqCst), 2 + 3);
+    }
+
+    #[test]
+    fn resolve_single_file() {
+        let files = vec![file(1, 10, "mod.zip")];
+        let custom = ResolvePolicy::custom(|_| panic!("called for a single file"));
+        assert_eq!(custom.select(&files), Some(0));
+        assert_eq!(ResolvePolicy::Fail.select(&files), Some(0));
+        assert_eq!(ResolvePolicy::Latest.select(&[]), None);
+    }
+
+    #[test]
+    fn filename_pattern() {
+        assert!(glob_match("*-linux.zip", "mod-1.0-linux.zip"));
+        assert!(!glob_match("*-linux.zip", "mod-1.0-windows.zip"));
+        assert!(glob_match("mod-?.zip", "mod-2.zip"));
+        assert!(!glob_match("mod-?.zip", "mod-10.zip"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYYbc"));
+        assert!(!glob_match("a*b", "ab-c"));
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn lenient_versions() {
+        let v = |s| parse_version(s).map(|v| v.to_string());
+        assert_eq!(v("1.2.3"), Some("1.2.3".to_string()));
+        assert_eq!(v("v1.2.3-beta"), Some("1.2.3-beta".to_string()));
+        assert_eq!(v("V2"), Some("2.0.0".to_string()));
+        assert_eq!(v(" 1.4 "), Some("1.4.0".to_string()));
+        assert_eq!(v("latest"), None);
+    }
+
+    #[cfg(feature = "semver")]
+    #[test]
+    fn select() {
+        let req = semver::VersionReq::parse(">=2.0, <3").unwrap();
+        let versions = vec![
+            Some("1.9"),
+            Some("v2.1.0"),
+            None,
+            Some("2.0.5"),
+            Some("final"),
+        ];
+        let (best, invalid) = select_version(versions, &req);
+        assert_eq!(best, Some(1));
+        assert_eq!(invalid, vec![4]);
+
+        let req = semver::VersionReq::parse("^3").unwrap();
+        let (best, _) = select_version(vec![Some("2.0")], &req);
+        assert_eq!(best, None);
+    }
+}
@@ -13,6 +13,7 @@ use reqwest::StatusCode;
 use serde_json::Error as JsonError;
 use url::ParseError;
 
+use crate::types::mods::{VirusPositive, VirusStatus};
 pub use crate::types::ClientError;
 
 pub type Result<T> = StdResult<T, Error>;
@@ -26,6 +27,9 @@ impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match *self.inner {
             ErrorKind::Download(ref e) => Some(e),
+            ErrorKind::Install(ref e) => Some(e),
+            ErrorKind::Dependency(ref e) => Some(e),
+            ErrorKind::Upload(ref e) => Some(e),
             ErrorKind::Fault { ref error, .. } => Some(error),
             ErrorKind::Http(ref e) => Some(e),
             ErrorKind::Reqwest(ref e) => Some(e),
@@ -70,24 +74,15 @@ impl Error {
     }
 
     pub fn is_serialization(&self) -> bool {
-        match *self.inner {
-            ErrorKind::Json(_) => true,
-            _ => false,
-        }
+        matches!(*self.inner, ErrorKind::Json(_))
     }
 
     pub fn is_authentication(&self) -> bool {
-        match *self.inner {
-            ErrorKind::Auth(_) => true,
-            _ => false,
-        }
+        matches!(*self.inner, ErrorKind::Auth(_))
     }
 
     pub fn is_validation(&self) -> bool {
-        match *self.inner {
-            ErrorKind::Validation(_, _) => true,
-            _ => false,
-        }
+        matches!(*self.inner, ErrorKind::Validation(_, _))
     }
 
     pub fn kind(&self) -> &ErrorKind {
@@ -108,6 +103,9 @@ pub enum ErrorKind {
         reset: Duration,
     },
     Download(DownloadError),
+    Install(InstallError),
+    Dependency(DependencyError),
+    Upload(UploadError),
     Json(JsonError),
     Http(HttpError),
     Reqwest(ReqwestError),
@@ -128,6 +126,9 @@ impl fmt::Display for ErrorKind {
                 write!(fmt, "API rate limit reached. Try again in {:?}.", reset)
             }
             ErrorKind::Download(e) => write!(fmt, "Download failed: {}", e),
+            ErrorKind::Install(e) => write!(fmt, "Install failed: {}", e),
+            ErrorKind::Dependency(e) => write!(fmt, "Dependency resolution failed: {}", e),
+            ErrorKind::Upload(e) => write!(fmt, "Upload failed: {}", e),
             ErrorKind::Json(e) => e.fmt(fmt),
             ErrorKind::Http(e) => e.fmt(fmt),
             ErrorKind::Reqwest(e) => e.fmt(fmt),
@@ -163,7 +164,9 @@ pub enum DownloadError {
         file_id: u32,
     },
     /// Multiple files for a given version were found and the policy was set to
-    /// [`ResolvePolicy::Fail`](../download/enum.ResolvePolicy.html#variant.Fail).
+    /// [`ResolvePolicy::Fail`](../download/enum.ResolvePolicy.html#variant.Fail) or the
+    /// [`ResolvePolicy::Custom`](../download/enum.ResolvePolicy.html#variant.Custom) closure
+    /// returned `None`.
     MultipleFilesFound {
         game_id: u32,
         mod_id: u32,
@@ -175,6 +178,22 @@ pub enum DownloadError {
         mod_id: u32,
         version: String,
     },
+    /// The file is not scanned or was flagged by the virus scanner and the policy was set to
+    /// [`VirusPolicy::Deny`](../download/enum.VirusPolicy.html#variant.Deny).
+    UnsafeFile {
+        mod_id: u32,
+        file_id: u32,
+        virus_status: VirusStatus,
+        virus_positive: VirusPositive,
+    },
+    /// No file matches a version requirement. `invalid` contains the versions that couldn't be
+    /// parsed as semver.
+    NoMatchingVersion {
+        game_id: u32,
+        mod_id: u32,
+        req: String,
+        invalid: Vec<String>,
+    },
 }
 
 impl StdError for DownloadError {}
@@ -214,6 +233,146 @@ impl fmt::Display for DownloadError {
                 "Mod {{id: {1}, game_id: {0}}}: No file with version '{2}' found.",
                 game_id, mod_id, version,
             ),
+            DownloadError::UnsafeFile {
+                mod_id,
+                file_id,
+                virus_status,
+                virus_positive,
+            } => write!(
+                fmt,
+                "Mod {{id: {}}}: File {{ id: {} }} is not safe to download \
+                 (virus_status: {:?}, virus_positive: {:?}).",
+                mod_id, file_id, virus_status, virus_positive,
+            ),
+            DownloadError::NoMatchingVersion {
+                game_id,
+                mod_id,
+                req,
+                invalid,
+            } => {
+                write!(
+                    fmt,
+                    "Mod {{id: {1}, game_id: {0}}}: No file matching version '{2}' found.",
+                    game_id, mod_id, req,
+                )?;
+                if !invalid.is_empty() {
+                    write!(fmt, " Invalid versions: {:?}", invalid)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum InstallError {
+    /// The archive contains an entry with an absolute path or a path leaving the target
+    /// directory.
+    UnsafePath { name: String },
+    /// The archive contains a symbolic link or a directory of an entry is a symbolic link in
+    /// the target directory.
+    Symlink { name: String },
+    /// The file of an entry already exists in the target directory.
+    Exists { name: String },
+    /// The uncompressed size of an entry or of all entries exceeds the limit.
+    TooLarge { name: String, limit: u64 },
+    /// The archive contains more entries than allowed.
+    TooManyEntries { limit: usize },
+    /// The archive is invalid or uses an unsupported feature.
+    InvalidArchive(String),
+}
+
+impl StdError for InstallError {}
+
+impl fmt::Display for InstallError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InstallError::UnsafePath { name } => write!(fmt, "Unsafe path in archive: '{}'", name),
+            InstallError::Symlink { name } => write!(fmt, "Symbolic link in archive: '{}'", name),
+            InstallError::Exists { name } => {
+                write!(fmt, "Archive entry '{}' already exists.", name)
+            }
+            InstallError::TooLarge { name, limit } => write!(
+                fmt,
+                "Archive entry '{}' exceeds the size limit of {} bytes.",
+                name, limit,
+            ),
+            InstallError::TooManyEntries { limit } => {
+                write!(fmt, "Archive has more than {} entries.", limit)
+            }
+            InstallError::InvalidArchive(msg) => write!(fmt, "Invalid archive: {}", msg),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DependencyError {
+    /// The dependencies form a cycle. The first and the last mod id are the same.
+    Cycle { mod_ids: Vec<u32> },
+    /// A dependency does not exist.
+    Missing { mod_id: u32, required_by: u32 },
+    /// A dependency is not accepted, hidden or has no primary file.
+    Unavailable { mod_id: u32, required_by: u32 },
+}
+
+impl StdError for DependencyError {}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyError::Cycle { mod_ids } => {
+                let ids = mod_ids.iter().map(u32::to_string).collect::<Vec<_>>();
+                write!(fmt, "Dependency cycle: {}", ids.join(" -> "))
+            }
+            DependencyError::Missing {
+                mod_id,
+                required_by,
+            } => write!(
+                fmt,
+                "Mod {{id: {}}} required by Mod {{id: {}}} not found.",
+                mod_id, required_by,
+            ),
+            DependencyError::Unavailable {
+                mod_id,
+                required_by,
+            } => write!(
+                fmt,
+                "Mod {{id: {}}} required by Mod {{id: {}}} is not available.",
+                mod_id, required_by,
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum UploadError {
+    /// The multipart upload session does not exist.
+    SessionNotFound { upload_id: String },
+    /// The multipart upload session was cancelled.
+    Cancelled { upload_id: String },
+    /// The size of the uploaded file does not match the parts already uploaded.
+    SizeMismatch { upload_id: String, part_number: u32 },
+}
+
+impl StdError for UploadError {}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadError::SessionNotFound { upload_id } => {
+                write!(fmt, "Upload session {{id: {}}} not found.", upload_id)
+            }
+            UploadError::Cancelled { upload_id } => {
+                write!(fmt, "Upload session {{id: {}}} was cancelled.", upload_id)
+            }
+            UploadError::SizeMismatch {
+                upload_id,
+                part_number,
+            } => write!(
+                fmt,
+                "Part {} of upload session {{id: {}}} does not match the file.",
+                part_number, upload_id,
+            ),
         }
     }
 }
@@ -228,9 +387,9 @@ impl fmt::Display for ClientError {
             for (k, v) in errors {
                 buf.push('\n');
                 buf.push_str("  ");
-                buf.push_str(&k);
+                buf.push_str(k);
                 buf.push_str(": ");
-                buf.push_str(&v);
+                buf.push_str(v);
             }
         }
         fmt::Display::fmt(&buf, f)
@@ -256,6 +415,10 @@ pub(crate) fn client(code: StatusCode, error: ClientError) -> Error {
     }
 }
 
+pub(crate) fn validation<S: Into<String>>(message: S, errors: HashMap<String, String>) -> Error {
+    Error::new(ErrorKind::Validation(message.into(), errors))
+}
+
 pub(crate) fn ratelimit(reset: u64) -> Error {
     Error::new(ErrorKind::RateLimit {
         reset: Duration::from_secs(reset * 60),
@@ -299,6 +462,109 @@ where
     }))
 }
 
+pub(crate) fn dependency_cycle(mod_ids: Vec<u32>) -> Error {
+    Error::new(ErrorKind::Dependency(DependencyError::Cycle { mod_ids }))
+}
+
+pub(crate) fn dependency_missing(mod_id: u32, required_by: u32) -> Error {
+    Error::new(ErrorKind::Dependency(DependencyError::Missing {
+        mod_id,
+        required_by,
+    }))
+}
+
+pub(crate) fn dependency_unavailable(mod_id: u32, required_by: u32) -> Error {
+    Error::new(ErrorKind::Dependency(DependencyError::Unavailable {
+        mod_id,
+        required_by,
+    }))
+}
+
+pub(crate) fn upload_session_not_found<S: Into<String>>(upload_id: S) -> Error {
+    Error::new(ErrorKind::Upload(UploadError::SessionNotFound {
+        upload_id: upload_id.into(),
+    }))
+}
+
+pub(crate) fn upload_cancelled<S: Into<String>>(upload_id: S) -> Error {
+    Error::new(ErrorKind::Upload(UploadError::Cancelled {
+        upload_id: upload_id.into(),
+    }))
+}
+
+pub(crate) fn upload_size_mismatch<S: Into<String>>(upload_id: S, part_number: u32) -> Error {
+    Error::new(ErrorKind::Upload(UploadError::SizeMismatch {
+        upload_id: upload_id.into(),
+        part_number,
+    }))
+}
+
+#[cfg(feature = "install")]
+pub(crate) fn install_unsafe_path<S: Into<String>>(name: S) -> Error {
+    Error::new(ErrorKind::Install(InstallError::UnsafePath {
+        name: name.into(),
+    }))
+}
+
+#[cfg(feature = "install")]
+pub(crate) fn install_symlink<S: Into<String>>(name: S) -> Error {
+    Error::new(ErrorKind::Install(InstallError::Symlink {
+        name: name.into(),
+    }))
+}
+
+#[cfg(feature = "install")]
+pub(crate) fn install_exists<S: Into<String>>(name: S) -> Error {
+    Error::new(ErrorKind::Install(InstallError::Exists {
+        name: name.into(),
+    }))
+}
+
+#[cfg(feature = "install")]
+pub(crate) fn install_too_large<S: Into<String>>(name: S, limit: u64) -> Error {
+    Error::new(ErrorKind::Install(InstallError::TooLarge {
+        name: name.into(),
+        limit,
+    }))
+}
+
+#[cfg(feature = "install")]
+pub(crate) fn install_too_many_entries(limit: usize) -> Error {
+    Error::new(ErrorKind::Install(InstallError::TooManyEntries { limit }))
+}
+
+#[cfg(feature = "install")]
+pub(crate) fn install_invalid_archive<S: Into<String>>(msg: S) -> Error {
+    Error::new(ErrorKind::Install(InstallError::InvalidArchive(msg.into())))
+}
+
+pub(crate) fn download_unsafe_file(file: &crate::files::File) -> Error {
+    Error::new(ErrorKind::Download(DownloadError::UnsafeFile {
+        mod_id: file.mod_id,
+        file_id: file.id,
+        virus_status: file.virus_status,
+        virus_positive: file.virus_positive,
+    }))
+}
+
+#[cfg(feature = "semver")]
+pub(crate) fn download_no_matching_version<S>(
+    game_id: u32,
+    mod_id: u32,
+    req: S,
+    invalid: Vec<String>,
+) -> Error
+where
+    S: Into<String>,
+{
+    Error::new(ErrorKind::Download(DownloadError::NoMatchingVersion {
+        game_id,
+        mod_id,
+        req: req.into(),
+        invalid,
+    }))
+}
+
 impl From<String> for ErrorKind {
     fn from(s: String) -> ErrorKind {
         ErrorKind::Message(s)
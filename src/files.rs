@@ -1,15 +1,30 @@
 //! Modfile interface
 use std::ffi::OsStr;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[cfg(feature = "directory")]
+use futures::stream;
 
 use mime::APPLICATION_OCTET_STREAM;
 use tokio_io::AsyncRead;
 use url::form_urlencoded;
 
-use crate::multipart::{FileSource, FileStream};
+#[cfg(feature = "directory")]
+use crate::archive::{Globs, ZipDirectory};
+use crate::blocking;
+use crate::error::Result;
+use crate::multipart::{Checksum, FileSource, FileStream, OnProgress};
 use crate::prelude::*;
+use crate::validation::{self, Validator};
+
+pub use crate::multipart::Progress;
+pub use crate::types::mods::{Download, File, FileHash, VirusPositive, VirusStatus};
+pub use crate::types::mods::{MultipartUpload, MultipartUploadPart, MultipartUploadStatus};
+pub use crate::upload::{UploadSession, CHUNK_SIZE};
 
-pub use crate::types::mods::{Download, File, FileHash};
+#[cfg(feature = "directory")]
+const APPLICATION_ZIP: &str = "application/zip";
 
 /// Interface for the modfiles the authenticated user uploaded.
 pub struct MyFiles {
@@ -24,7 +39,7 @@ impl MyFiles {
     /// Return all modfiles the authenticated user uploaded. [required: token]
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<File>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Files>>) -> Future<List<File>> {
         token_required!(self.modio);
         let mut uri = vec!["/me/files".to_owned()];
         let query = filter.to_query_string();
@@ -37,7 +52,7 @@ impl MyFiles {
     /// Provides a stream over all modfiles the authenticated user uploaded. [required: token]
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<File> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Files>>) -> Stream<File> {
         token_required!(s self.modio);
         let mut uri = vec!["/me/files".to_owned()];
         let query = filter.to_query_string();
@@ -71,7 +86,7 @@ impl Files {
     /// Return all files that are published for a mod this `Files` refers to.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<File>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Files>>) -> Future<List<File>> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -83,7 +98,7 @@ impl Files {
     /// Provides a stream over all files that are published for a mod this `Files` refers to.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<File> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Files>>) -> Stream<File> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -92,18 +107,88 @@ impl Files {
         self.modio.stream(&uri.join("?"))
     }
 
+    /// Provides a stream over all files of the mod that pass any of the alternatives.
+    ///
+    /// See [OR-queries](../filter/any/index.html).
+    pub fn iter_any(&self, filter: &AnyOf<impl Scope<endpoint::Files>>) -> Stream<File> {
+        self.modio.stream_any(&self.path(""), filter)
+    }
+
     /// Return a reference to a file.
     pub fn get(&self, id: u32) -> FileRef {
         FileRef::new(self.modio.clone(), self.game, self.mod_id, id)
     }
 
     /// Add a file for a mod that this `Files` refers to. [required: token]
-    pub fn add(&self, options: AddFileOptions) -> Future<File> {
+    ///
+    /// The md5 hash of the file is computed while uploading and sent as `filehash` unless it was
+    /// set with [`AddFileOptions::filehash`](struct.AddFileOptions.html#method.filehash).
+    pub fn add(&self, options: AddFileOptions) -> Future<UploadedFile> {
         token_required!(self.modio);
-        self.modio.post_form(&self.path(""), options)
+        let (modio, path) = (self.modio.clone(), self.path(""));
+        // Validating reads the file and walks the directory, done on a separate thread.
+        let form = blocking::run(move || {
+            let entries = options.check()?;
+            Ok(options.into_form(entries))
+        });
+        Box::new(form.and_then(move |(form, checksum)| {
+            modio
+                .post_form(&path, form)
+                .map(move |file: File| match checksum {
+                    Some(checksum) => UploadedFile {
+                        file,
+                        md5: checksum.md5().unwrap_or_default(),
+                        size: checksum.size(),
+                    },
+                    None => UploadedFile {
+                        md5: file.filehash.md5.clone(),
+                        size: file.filesize,
+                        file,
+                    },
+                })
+        }))
+    }
+
+    /// Create a multipart upload session for a large file. [required: token]
+    ///
+    /// See [`UploadSession`](struct.UploadSession.html).
+    pub fn create_upload_session<S: Into<String>>(&self, filename: S) -> Future<UploadSession> {
+        token_required!(self.modio);
+        let params = form_urlencoded::Serializer::new(String::new())
+            .append_pair("filename", &filename.into())
+            .finish();
+        let (modio, game, mod_id) = (self.modio.clone(), self.game, self.mod_id);
+        Box::new(self.modio.post(&self.path("/multipart"), params).map(
+            move |upload: MultipartUpload| {
+                UploadSession::new(modio, game, mod_id, upload.upload_id)
+            },
+        ))
+    }
+
+    /// Return a reference to an existing multipart upload session, e.g. to resume an upload.
+    pub fn upload_session<S: Into<String>>(&self, upload_id: S) -> UploadSession {
+        UploadSession::new(self.modio.clone(), self.game, self.mod_id, upload_id.into())
+    }
+
+    /// Provides a stream over the multipart upload sessions of the mod. [required: token]
+    pub fn upload_sessions(&self) -> Stream<MultipartUpload> {
+        token_required!(s self.modio);
+        self.modio.stream(&self.path("/multipart/sessions"))
     }
 }
 
+/// The result of [`Files::add`](struct.Files.html#method.add).
+#[derive(Debug)]
+pub struct UploadedFile {
+    /// The newly created modfile.
+    pub file: File,
+    /// The md5 hash of the uploaded data computed by the client or the hash reported by mod.io
+    /// for files uploaded with an [`UploadSession`](struct.UploadSession.html).
+    pub md5: String,
+    /// The number of uploaded bytes.
+    pub size: u64,
+}
+
 /// Reference interface of a modfile.
 pub struct FileRef {
     modio: Modio,
@@ -182,8 +267,18 @@ impl FileRef {
 ///
 /// let filter = Id::_in(vec![1, 2]).order_by(Id::desc());
 /// ```
+///
+/// Only files that have been scanned without detecting a threat:
+/// ```
+/// use modio::filter::prelude::*;
+/// use modio::files::filters::{VirusPositive, VirusStatus};
+///
+/// let filter = VirusStatus::scan_complete().and(VirusPositive::no_threats());
+/// ```
 #[rustfmt::skip]
 pub mod filters {
+    use crate::filter::prelude::*;
+
     #[doc(inline)]
     pub use crate::filter::prelude::Fulltext;
     #[doc(inline)]
@@ -193,23 +288,81 @@ pub mod filters {
     #[doc(inline)]
     pub use crate::filter::prelude::DateAdded;
 
-    filter!(DateScanned, DATE_SCANNED, "date_scanned", Eq, NotEq, In, Cmp);
-    filter!(VirusStatus, VIRUS_STATUS, "virus_status", Eq, NotEq, In, Cmp);
-    filter!(VirusPositive, VIRUS_POSITIVE, "virus_positive", Eq, NotEq, In, Cmp);
-    filter!(Filesize, FILESIZE, "filesize", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(Filehash, FILEHASH, "filehash", Eq, NotEq, In, Like);
-    filter!(Filename, FILENAME, "filename", Eq, NotEq, In, Like);
-    filter!(Version, VERSION, "version", Eq, NotEq, In, Like, OrderBy);
-    filter!(Changelog, CHANGELOG, "changelog", Eq, NotEq, In, Like);
+    type Endpoint = crate::filter::endpoint::Files;
+
+    filter!(DateScanned, DATE_SCANNED, "date_scanned", crate::filter::Timestamp: Eq, NotEq, In, Cmp);
+    filter!(VirusStatus, VIRUS_STATUS, "virus_status", crate::types::mods::VirusStatus: Eq, NotEq, In, Cmp);
+    filter!(VirusPositive, VIRUS_POSITIVE, "virus_positive", crate::types::mods::VirusPositive: Eq, NotEq, In, Cmp);
+    filter!(Filesize, FILESIZE, "filesize", u64: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(Filehash, FILEHASH, "filehash", String: Eq, NotEq, In, Like);
+    filter!(Filename, FILENAME, "filename", String: Eq, NotEq, In, Like);
+    filter!(Version, VERSION, "version", String: Eq, NotEq, In, Like, OrderBy);
+    filter!(Changelog, CHANGELOG, "changelog", String: Eq, NotEq, In, Like);
+
+    /// The modfile fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+    pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+        Fulltext::FIELD,
+        Id::FIELD,
+        ModId::FIELD,
+        DateAdded::FIELD,
+        DateScanned::FIELD,
+        VirusStatus::FIELD,
+        VirusPositive::FIELD,
+        Filesize::FIELD,
+        Filehash::FIELD,
+        Filename::FIELD,
+        Version::FIELD,
+        Changelog::FIELD,
+    ]);
+
+    impl VirusStatus {
+        pub fn not_scanned() -> Filter<Endpoint> {
+            VirusStatus::eq(crate::files::VirusStatus::NotScanned)
+        }
+
+        pub fn scan_complete() -> Filter<Endpoint> {
+            VirusStatus::eq(crate::files::VirusStatus::ScanComplete)
+        }
+
+        pub fn in_progress() -> Filter<Endpoint> {
+            VirusStatus::eq(crate::files::VirusStatus::InProgress)
+        }
+
+        pub fn too_large_to_scan() -> Filter<Endpoint> {
+            VirusStatus::eq(crate::files::VirusStatus::TooLargeToScan)
+        }
+
+        pub fn file_not_found() -> Filter<Endpoint> {
+            VirusStatus::eq(crate::files::VirusStatus::FileNotFound)
+        }
+
+        pub fn error_scanning() -> Filter<Endpoint> {
+            VirusStatus::eq(crate::files::VirusStatus::ErrorScanning)
+        }
+    }
+
+    impl VirusPositive {
+        pub fn no_threats() -> Filter<Endpoint> {
+            VirusPositive::eq(crate::files::VirusPositive::NoThreats)
+        }
+
+        pub fn malicious() -> Filter<Endpoint> {
+            VirusPositive::eq(crate::files::VirusPositive::Malicious)
+        }
+    }
 }
 
 pub struct AddFileOptions {
-    source: FileSource,
+    source: Option<FileSource>,
+    upload_id: Option<String>,
     version: Option<String>,
     changelog: Option<String>,
     active: Option<bool>,
     filehash: Option<String>,
     metadata_blob: Option<String>,
+    progress: Option<Arc<OnProgress>>,
+    #[cfg(feature = "directory")]
+    directory: Option<(PathBuf, Globs)>,
 }
 
 impl AddFileOptions {
@@ -219,16 +372,20 @@ impl AddFileOptions {
         S: Into<String>,
     {
         AddFileOptions {
-            source: FileSource {
+            source: Some(FileSource {
                 inner: FileStream::new(inner),
                 filename: filename.into(),
                 mime: APPLICATION_OCTET_STREAM,
-            },
+            }),
+            upload_id: None,
             version: None,
             changelog: None,
             active: None,
             filehash: None,
             metadata_blob: None,
+            progress: None,
+            #[cfg(feature = "directory")]
+            directory: None,
         }
     }
 
@@ -250,24 +407,176 @@ impl AddFileOptions {
         let file = file.as_ref();
 
         AddFileOptions {
-            source: FileSource {
-                inner: FileStream::open(file),
-                filename: filename.into(),
-                mime: APPLICATION_OCTET_STREAM,
-            },
+            source: Some(FileSource::open(
+                file,
+                filename.into(),
+                APPLICATION_OCTET_STREAM,
+            )),
+            upload_id: None,
             version: None,
             changelog: None,
             active: None,
             filehash: None,
             metadata_blob: None,
+            progress: None,
+            #[cfg(feature = "directory")]
+            directory: None,
         }
     }
 
+    /// Add the file uploaded with a completed multipart upload session.
+    ///
+    /// See [`UploadSession`](struct.UploadSession.html).
+    pub fn with_upload_session<S: Into<String>>(upload_id: S) -> AddFileOptions {
+        AddFileOptions {
+            source: None,
+            upload_id: Some(upload_id.into()),
+            version: None,
+            changelog: None,
+            active: None,
+            filehash: None,
+            metadata_blob: None,
+            progress: None,
+            #[cfg(feature = "directory")]
+            directory: None,
+        }
+    }
+
+    /// Upload the contents of a directory as zip archive named `<directory name>.zip`.
+    ///
+    /// The archive is built while uploading without creating a temporary file. Files are added
+    /// in sorted order with a fixed timestamp, so the same directory always produces the same
+    /// archive and md5 hash. Symbolic links are skipped and the upload fails once the archive
+    /// exceeds the maximum modfile size of 500 MB.
+    ///
+    /// Requires the `directory` feature.
+    #[cfg(feature = "directory")]
+    pub fn with_directory<P: AsRef<Path>>(dir: P) -> AddFileOptions {
+        let dir = dir.as_ref();
+        let filename = dir.file_name().and_then(OsStr::to_str).map_or_else(
+            || String::from("modfile.zip"),
+            |name| format!("{}.zip", name),
+        );
+
+        AddFileOptions {
+            source: Some(FileSource {
+                inner: FileStream::from_stream(stream::empty()),
+                filename,
+                mime: APPLICATION_ZIP.parse().expect("application/zip"),
+            }),
+            upload_id: None,
+            version: None,
+            changelog: None,
+            active: None,
+            filehash: None,
+            metadata_blob: None,
+            progress: None,
+            directory: Some((dir.to_path_buf(), Globs::default())),
+        }
+    }
+
+    /// Only add the files of the directory matching the glob pattern, e.g. `**/*.lua`.
+    ///
+    /// Patterns are matched against the `/` separated path relative to the directory. Without
+    /// include patterns all files are added. Has no effect unless created with
+    /// [`with_directory`](#method.with_directory).
+    #[cfg(feature = "directory")]
+    pub fn include<S: Into<String>>(mut self, pattern: S) -> Self {
+        if let Some((_, ref mut globs)) = self.directory {
+            globs.include.push(pattern.into());
+        }
+        self
+    }
+
+    /// Skip the files of the directory matching the glob pattern, e.g. `**/.git/**`.
+    ///
+    /// Has no effect unless created with [`with_directory`](#method.with_directory).
+    #[cfg(feature = "directory")]
+    pub fn exclude<S: Into<String>>(mut self, pattern: S) -> Self {
+        if let Some((_, ref mut globs)) = self.directory {
+            globs.exclude.push(pattern.into());
+        }
+        self
+    }
+
     option!(version);
     option!(changelog);
     option!(active: bool);
     option!(filehash);
     option!(metadata_blob);
+
+    /// Report the upload progress of the file to the callback.
+    pub fn progress<F>(self, f: F) -> Self
+    where
+        F: Fn(Progress<'_>) + Send + Sync + 'static,
+    {
+        Self {
+            progress: Some(Arc::new(f)),
+            ..self
+        }
+    }
+}
+
+impl AddFileOptions {
+    /// Check the text fields and the size of the file before uploading it.
+    ///
+    /// The file must be a zip archive containing at least one file. Files added with
+    /// [`with_read`](#method.with_read) are not checked.
+    pub fn validate(&self) -> Result<()> {
+        self.check().map(|_| ())
+    }
+
+    /// Validate the options and return the files of the directory to upload.
+    fn check(&self) -> Result<Option<Vec<(String, PathBuf)>>> {
+        let mut v = Validator::new();
+        v.max_len("version", self.version.as_deref(), 50);
+        v.max_len("changelog", self.changelog.as_deref(), 50000);
+        v.max_len("metadata_blob", self.metadata_blob.as_deref(), 50000);
+        if let Some(ref source) = self.source {
+            v.archive("filedata", source, validation::MAX_MODFILE_SIZE);
+        }
+        #[cfg_attr(not(feature = "directory"), allow(unused_mut))]
+        let mut listing = None;
+        #[cfg(feature = "directory")]
+        {
+            if let Some((ref dir, ref globs)) = self.directory {
+                match crate::archive::list(dir, globs) {
+                    Ok(ref entries) if entries.is_empty() => {
+                        v.error("filedata", "The directory does not contain any files.")
+                    }
+                    Ok(entries) => listing = Some(entries),
+                    Err(e) => v.error(
+                        "filedata",
+                        format!("The directory could not be read: {}", e),
+                    ),
+                }
+            }
+        }
+        v.finish()?;
+        Ok(listing)
+    }
+
+    fn into_form(mut self, entries: Option<Vec<(String, PathBuf)>>) -> (Form, Option<Checksum>) {
+        #[cfg(feature = "directory")]
+        {
+            if let (Some(entries), Some(source)) = (entries, &mut self.source) {
+                // Reading and compressing the files is done on a separate thread.
+                let zip = ZipDirectory::new(entries, validation::MAX_MODFILE_SIZE);
+                source.inner = FileStream::from_stream(blocking::iter(zip));
+            }
+        }
+        #[cfg(not(feature = "directory"))]
+        let _ = entries;
+        let checksum = self.source.as_mut().map(|s| s.inner.checksum());
+        let filehash = self.filehash.take();
+        let mut form = Form::from(self);
+        form = match (filehash, &checksum) {
+            (Some(filehash), _) => form.text("filehash", filehash),
+            (None, Some(checksum)) => form.part("filehash", checksum.clone().into_part()),
+            (None, None) => form,
+        };
+        (form, checksum)
+    }
 }
 
 #[doc(hidden)]
@@ -289,7 +598,16 @@ impl From<AddFileOptions> for Form {
         if let Some(metadata_blob) = opts.metadata_blob {
             form = form.text("metadata_blob", metadata_blob);
         }
-        form.part("filedata", opts.source.into())
+        if let Some(upload_id) = opts.upload_id {
+            form = form.text("upload_id", upload_id);
+        }
+        match opts.source {
+            Some(source) => {
+                let source = source.with_progress(opts.progress.as_ref());
+                form.part("filedata", source.into())
+            }
+            None => form,
+        }
     }
 }
 
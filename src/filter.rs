@@ -1,22 +1,37 @@
 //! Filtering and sorting
 use std::collections::BTreeSet;
+use std::error::Error as StdError;
 use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+// The filters of a field are marked with the `Endpoint` type alias of the invoking module.
 macro_rules! filter {
-    ($type:ident, $name:ident, $value:expr) => {
-        static $name: &str = $value;
-        pub struct $type;
-    };
     (
         $(#[$outer:meta])*
-        $type:ident, $name:ident, $value:expr, $($x:tt),*) => {
+        $type:ident, $name:ident, $value:expr, $vt:ty: $($x:tt),*) => {
         static $name: &str = $value;
         $(#[$outer])*
         pub struct $type;
+
+        impl crate::filter::FilterField for $type {
+            type Value = $vt;
+            type Endpoint = Endpoint;
+        }
         $(
             __impl_filter!($x, $type, $name);
         )*
 
+        impl $type {
+            /// Description of the field for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+            pub const FIELD: crate::filter::expr::Field = crate::filter::expr::Field::new(
+                $value,
+                <$vt as crate::filter::FilterValue>::KIND,
+                &[$(crate::filter::expr::Capability::$x),*],
+            );
+        }
+
         impl crate::private::Sealed for $type {}
     };
 }
@@ -49,13 +64,12 @@ macro_rules! __impl_filter {
 macro_rules! __impl_filter_eq {
     ($type:ty, $name:expr) => {
         impl crate::filter::Eq for $type {
-            fn eq<T, V>(value: V) -> crate::filter::Filter
+            fn eq<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::Equals;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
     };
@@ -64,13 +78,12 @@ macro_rules! __impl_filter_eq {
 macro_rules! __impl_filter_ne {
     ($type:ty, $name:expr) => {
         impl crate::filter::NotEq for $type {
-            fn ne<T, V>(value: V) -> crate::filter::Filter
+            fn ne<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::Not;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
     };
@@ -79,24 +92,22 @@ macro_rules! __impl_filter_ne {
 macro_rules! __impl_filter_like {
     ($type:ty, $name:expr) => {
         impl crate::filter::Like for $type {
-            fn like<T, V>(value: V) -> crate::filter::Filter
+            fn like<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::Like;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
 
         impl crate::filter::NotLike for $type {
-            fn not_like<T, V>(value: V) -> crate::filter::Filter
+            fn not_like<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::NotLike;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
     };
@@ -105,24 +116,22 @@ macro_rules! __impl_filter_like {
 macro_rules! __impl_filter_in {
     ($type:ty, $name:expr) => {
         impl crate::filter::In for $type {
-            fn _in<T, V>(value: V) -> crate::filter::Filter
+            fn _in<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::In;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
 
         impl crate::filter::NotIn for $type {
-            fn not_in<T, V>(value: V) -> crate::filter::Filter
+            fn not_in<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::NotIn;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
     };
@@ -131,40 +140,36 @@ macro_rules! __impl_filter_in {
 macro_rules! __impl_filter_cmp {
     ($type:ty, $name:expr) => {
         impl crate::filter::Cmp for $type {
-            fn le<T, V>(value: V) -> crate::filter::Filter
+            fn le<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::Max;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
 
-            fn ge<T, V>(value: V) -> crate::filter::Filter
+            fn ge<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::Min;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
 
-            fn gt<T, V>(value: V) -> crate::filter::Filter
+            fn gt<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::GreaterThan;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
 
-            fn lt<T, V>(value: V) -> crate::filter::Filter
+            fn lt<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::SmallerThan;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
     };
@@ -173,13 +178,12 @@ macro_rules! __impl_filter_cmp {
 macro_rules! __impl_filter_bit {
     ($type:ty, $name:expr) => {
         impl crate::filter::BitwiseAnd for $type {
-            fn bit_and<T, V>(value: V) -> crate::filter::Filter
+            fn bit_and<V>(value: V) -> crate::filter::Filter<Self::Endpoint>
             where
-                T: std::fmt::Display,
-                V: Into<crate::filter::OneOrMany<T>>,
+                V: crate::filter::IntoValues<Self::Value>,
             {
                 let op = crate::filter::Operator::BitwiseAnd;
-                crate::filter::Filter::new($name, op, value)
+                crate::filter::Filter::new($name, op, value.into_values())
             }
         }
     };
@@ -188,11 +192,11 @@ macro_rules! __impl_filter_bit {
 macro_rules! __impl_filter_order_by {
     ($type:ty, $name:expr) => {
         impl crate::filter::OrderBy for $type {
-            fn asc() -> crate::filter::Filter {
+            fn asc() -> crate::filter::Filter<Self::Endpoint> {
                 crate::filter::Filter::new_order_by_asc($name)
             }
 
-            fn desc() -> crate::filter::Filter {
+            fn desc() -> crate::filter::Filter<Self::Endpoint> {
                 crate::filter::Filter::new_order_by_desc($name)
             }
         }
@@ -200,6 +204,11 @@ macro_rules! __impl_filter_order_by {
 }
 // }}}
 
+pub mod any;
+pub mod endpoint;
+pub mod expr;
+pub mod matches;
+
 /// A `prelude` for using common filters and importing traits.
 /// ```
 /// use modio::filter::prelude::*;
@@ -216,18 +225,20 @@ pub mod prelude {
     pub use super::Filter;
     pub use super::OneOrMany;
 
-    filter!(Fulltext, _Q, "_q", Eq);
-    filter!(Id, ID, "id", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(Name, NAME, "name", Eq, NotEq, Like, In, OrderBy);
-    filter!(NameId, NAME_ID, "name_id", Eq, NotEq, Like, In, OrderBy);
-    filter!(ModId, MOD_ID, "mod_id", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(Status, STATUS, "status", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(DateAdded, DATE_ADDED, "date_added", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(DateUpdated, DATE_UPDATED, "date_updated", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(DateLive, DATE_LIVE, "date_live", Eq, NotEq, In, Cmp, OrderBy);
+    type Endpoint = super::endpoint::Any;
+
+    filter!(Fulltext, _Q, "_q", String: Eq);
+    filter!(Id, ID, "id", u32: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(Name, NAME, "name", String: Eq, NotEq, Like, In, OrderBy);
+    filter!(NameId, NAME_ID, "name_id", String: Eq, NotEq, Like, In, OrderBy);
+    filter!(ModId, MOD_ID, "mod_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(Status, STATUS, "status", crate::types::Status: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(DateAdded, DATE_ADDED, "date_added", crate::filter::Timestamp: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(DateUpdated, DATE_UPDATED, "date_updated", crate::filter::Timestamp: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(DateLive, DATE_LIVE, "date_live", crate::filter::Timestamp: Eq, NotEq, In, Cmp, OrderBy);
     filter!(
         /// Unique id of the user who has ownership of the objects.
-        SubmittedBy, SUBMITTED_BY, "submitted_by", Eq, NotEq, In, Cmp, OrderBy
+        SubmittedBy, SUBMITTED_BY, "submitted_by", u32: Eq, NotEq, In, Cmp, OrderBy
     );
 
     /// Create a `Filter` with a limit to paginate through results.
@@ -253,65 +264,99 @@ pub mod prelude {
     }
 }
 
-pub trait Eq: crate::private::Sealed {
+/// A filter field and the type of its values.
+///
+/// Values of the wrong type are rejected at compile time, use
+/// [`custom_filter`](fn.custom_filter.html) for untyped values.
+///
+/// ```
+/// use std::time::SystemTime;
+/// use modio::filter::prelude::*;
+/// use modio::mods::filters::Visible;
+/// use modio::mods::Visibility;
+///
+/// let filter = DateAdded::lt(SystemTime::now()).and(Visible::eq(Visibility::Public));
+/// ```
+///
+/// ```compile_fail
+/// use modio::filter::prelude::*;
+///
+/// let filter = DateAdded::eq("banana");
+/// ```
+///
+/// ```compile_fail
+/// use modio::filter::prelude::*;
+/// use modio::mods::filters::Visible;
+///
+/// let filter = Visible::eq(42);
+/// ```
+pub trait FilterField: crate::private::Sealed {
+    type Value: FilterValue;
+    /// The endpoint marker of the filters created by the field.
+    type Endpoint;
+}
+
+pub trait Eq: FilterField {
     /// Creates [`Equals`](enum.Operator.html#variant.Equals) filter.
-    fn eq<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn eq<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait NotEq: crate::private::Sealed {
+pub trait NotEq: FilterField {
     /// Creates [`Not`](enum.Operator.html#variant.Not) filter.
-    fn ne<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn ne<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait Like: crate::private::Sealed {
+pub trait Like: FilterField {
     /// Creates [`Like`](enum.Operator.html#variant.Like) filter.
-    fn like<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn like<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait NotLike: crate::private::Sealed {
+pub trait NotLike: FilterField {
     /// Creates [`NotLike`](enum.Operator.html#variant.Like) filter.
-    fn not_like<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn not_like<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait In: crate::private::Sealed {
+pub trait In: FilterField {
     /// Creates [`In`](enum.Operator.html#variant.In) filter.
-    fn _in<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn _in<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait NotIn: crate::private::Sealed {
+pub trait NotIn: FilterField {
     /// Creates [`NotIn`](enum.Operator.html#variant.NotIn) filter.
-    fn not_in<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn not_in<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait Cmp: crate::private::Sealed {
+pub trait Cmp: FilterField {
     /// Creates [`Max`](enum.Operator.html#variant.Max) filter.
-    fn le<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn le<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 
     /// Creates [`SmallerThan`](enum.Operator.html#variant.SmallerThan) filter.
-    fn lt<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn lt<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 
     /// Creates [`Min`](enum.Operator.html#variant.Min) filter.
-    fn ge<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn ge<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 
     /// Creates [`GreaterThan`](enum.Operator.html#variant.GreaterThan) filter.
-    fn gt<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn gt<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait BitwiseAnd: crate::private::Sealed {
+pub trait BitwiseAnd: FilterField {
     /// Creates [`BitwiseAnd`](enum.Operator.html#variant.BitwiseAnd) filter.
-    fn bit_and<T: fmt::Display, V: Into<OneOrMany<T>>>(value: V) -> Filter;
+    fn bit_and<V: IntoValues<Self::Value>>(value: V) -> Filter<Self::Endpoint>;
 }
 
-pub trait OrderBy: crate::private::Sealed {
+pub trait OrderBy: FilterField {
     /// Creates sorting filter in ascending order.
-    fn asc() -> Filter;
+    fn asc() -> Filter<Self::Endpoint>;
 
     /// Creates sorting filter in descending order.
-    fn desc() -> Filter;
+    fn desc() -> Filter<Self::Endpoint>;
 }
 
 /// Create a custom `Filter`.
 ///
+/// Unlike the filters of the endpoint modules the value isn't typed.
+///
 /// ```
 /// use modio::filter::{custom_filter, Operator};
 ///
@@ -350,16 +395,45 @@ pub fn custom_order_by_desc<S: Into<String>>(name: S) -> Filter {
     Filter::new_order_by_desc(name)
 }
 
-#[derive(Default)]
-pub struct Filter {
+/// Filters, sorting and pagination for the list endpoints.
+///
+/// The endpoint `E` is a marker from the [`endpoint`](endpoint/index.html) module. The filters of
+/// an endpoint's `filters` module can only be combined with each other and with the shared
+/// filters of the [`prelude`](prelude/index.html), which are usable for every endpoint.
+///
+/// ```compile_fail
+/// use modio::filter::prelude::*;
+/// use modio::files::filters::Filesize;
+/// use modio::mods::filters::Tags;
+///
+/// let filter = Tags::eq("Map").and(Filesize::gt(1024));
+/// ```
+pub struct Filter<E = endpoint::Any> {
     filters: BTreeSet<FilterEntry>,
     order_by: Option<Sorting>,
     limit: Option<usize>,
     offset: Option<usize>,
+    endpoint: PhantomData<E>,
 }
 
-impl Filter {
-    pub(crate) fn new<S, T, V>(name: S, op: Operator, value: V) -> Filter
+impl Default for Filter {
+    fn default() -> Filter {
+        Filter::empty()
+    }
+}
+
+impl<E> Filter<E> {
+    fn empty() -> Filter<E> {
+        Filter {
+            filters: BTreeSet::new(),
+            order_by: None,
+            limit: None,
+            offset: None,
+            endpoint: PhantomData,
+        }
+    }
+
+    pub(crate) fn new<S, T, V>(name: S, op: Operator, value: V) -> Filter<E>
     where
         S: Into<String>,
         T: fmt::Display,
@@ -369,70 +443,89 @@ impl Filter {
         filters.insert(FilterEntry::new(name.into(), op, value.into().to_string()));
         Filter {
             filters,
-            ..Default::default()
+            ..Filter::empty()
         }
     }
 
-    pub(crate) fn new_order_by_asc<S>(name: S) -> Filter
+    pub(crate) fn new_order_by_asc<S>(name: S) -> Filter<E>
     where
         S: Into<String>,
     {
         Filter {
             order_by: Some(Sorting::Asc(name.into())),
-            ..Default::default()
+            ..Filter::empty()
         }
     }
 
-    pub(crate) fn new_order_by_desc<S>(name: S) -> Filter
+    pub(crate) fn new_order_by_desc<S>(name: S) -> Filter<E>
     where
         S: Into<String>,
     {
         Filter {
             order_by: Some(Sorting::Desc(name.into())),
-            ..Default::default()
+            ..Filter::empty()
         }
     }
 
-    pub(crate) fn with_limit(limit: usize) -> Filter {
+    pub(crate) fn with_limit(limit: usize) -> Filter<E> {
         Filter {
             limit: Some(limit),
-            ..Default::default()
+            ..Filter::empty()
         }
     }
 
-    pub(crate) fn with_offset(offset: usize) -> Filter {
+    pub(crate) fn with_offset(offset: usize) -> Filter<E> {
         Filter {
             offset: Some(offset),
-            ..Default::default()
+            ..Filter::empty()
+        }
+    }
+
+    /// Change the endpoint marker without checking the filters.
+    pub(crate) fn cast<E2>(self) -> Filter<E2> {
+        Filter {
+            filters: self.filters,
+            order_by: self.order_by,
+            limit: self.limit,
+            offset: self.offset,
+            endpoint: PhantomData,
         }
     }
 
-    pub fn and(self, mut other: Filter) -> Filter {
+    pub fn and<E2>(self, mut other: Filter<E2>) -> Filter<E::Output>
+    where
+        E: endpoint::Merge<E2>,
+    {
         let Filter { mut filters, .. } = self;
-        filters.append(&mut other.filters);
+        for f in std::mem::take(&mut other.filters) {
+            filters.replace(f);
+        }
         Filter {
             filters,
             order_by: other.order_by.or(self.order_by),
             limit: other.limit.or(self.limit),
             offset: other.offset.or(self.offset),
+            endpoint: PhantomData,
         }
     }
 
-    pub fn order_by(self, other: Filter) -> Filter {
-        Filter {
-            order_by: other.order_by.or(self.order_by),
-            ..self
-        }
+    pub fn order_by<E2>(self, other: Filter<E2>) -> Filter<E::Output>
+    where
+        E: endpoint::Merge<E2>,
+    {
+        let mut filter = self.cast();
+        filter.order_by = other.order_by.or(filter.order_by);
+        filter
     }
 
-    pub fn limit(self, limit: usize) -> Filter {
+    pub fn limit(self, limit: usize) -> Filter<E> {
         Filter {
             limit: Some(limit),
             ..self
         }
     }
 
-    pub fn offset(self, offset: usize) -> Filter {
+    pub fn offset(self, offset: usize) -> Filter<E> {
         Filter {
             offset: Some(offset),
             ..self
@@ -440,7 +533,126 @@ impl Filter {
     }
 }
 
-impl crate::QueryString for Filter {
+impl Filter {
+    /// Parse a `Filter` from a query string created with
+    /// [`to_query_string`](../trait.QueryString.html#tymethod.to_query_string).
+    ///
+    /// The operator of a filter is taken from the suffix of the parameter name, `_sort` with an
+    /// optional `-` prefix for descending order, `_limit` and `_offset` are parsed as sorting and
+    /// pagination. The values of `-in` and `-not-in` filters are split at `,`.
+    ///
+    /// mod.io splits these lists at `,` as well, so a value containing a comma can't be part of
+    /// an `-in` or `-not-in` filter. `Name::_in(vec!["a,b"])` is sent and parsed back as the two
+    /// values `a` and `b`.
+    ///
+    /// ```
+    /// use modio::filter::Filter;
+    /// use modio::QueryString;
+    ///
+    /// let query = "id-in=1%2C2&name-lk=foo*&_sort=-date_added&_limit=10";
+    /// let filter = Filter::from_query_string(query)?;
+    /// assert_eq!(filter.to_query_string(), query);
+    /// # Ok::<_, modio::filter::ParseFilterError>(())
+    /// ```
+    pub fn from_query_string(query: &str) -> Result<Filter, ParseFilterError> {
+        let query = query.trim_start_matches('?');
+        let mut filter = Filter::default();
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match &*key {
+                "_sort" => {
+                    let sorting = match value.strip_prefix('-') {
+                        Some(field) => Sorting::Desc(field.to_owned()),
+                        None => Sorting::Asc(value.to_string()),
+                    };
+                    match sorting {
+                        Sorting::Asc(ref field) | Sorting::Desc(ref field) if field.is_empty() => {
+                            return Err(ParseFilterError::new(key, value));
+                        }
+                        _ => filter.order_by = Some(sorting),
+                    }
+                }
+                "_limit" => match value.parse() {
+                    Ok(limit) => filter.limit = Some(limit),
+                    Err(_) => return Err(ParseFilterError::new(key, value)),
+                },
+                "_offset" => match value.parse() {
+                    Ok(offset) => filter.offset = Some(offset),
+                    Err(_) => return Err(ParseFilterError::new(key, value)),
+                },
+                _ => {
+                    let (name, op) = Operator::split(&key);
+                    if name.is_empty() {
+                        return Err(ParseFilterError::new(key, value));
+                    }
+                    let value = match op {
+                        Operator::In | Operator::NotIn => {
+                            OneOrMany::Many(value.split(',').map(ToOwned::to_owned).collect())
+                        }
+                        _ => OneOrMany::One(value.into_owned()),
+                    };
+                    filter
+                        .filters
+                        .replace(FilterEntry::new(name.to_owned(), op, value));
+                }
+            }
+        }
+        Ok(filter)
+    }
+
+    /// Compile a [filter expression](expr/index.html) checked against the fields of an endpoint.
+    ///
+    /// ```
+    /// use modio::filter::Filter;
+    /// use modio::files::filters::SCHEMA;
+    ///
+    /// let filter = Filter::from_expr(r#"version ~ "1.*" order by -date_added"#, &SCHEMA)?;
+    /// # Ok::<_, modio::filter::expr::ParseError>(())
+    /// ```
+    pub fn from_expr<E>(
+        expr: &str,
+        schema: &expr::Schema<E>,
+    ) -> Result<Filter<E>, expr::ParseError> {
+        expr::parse(expr, schema.fields()).map(Filter::cast)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseFilterError;
+
+    fn from_str(s: &str) -> Result<Filter, ParseFilterError> {
+        Filter::from_query_string(s)
+    }
+}
+
+/// The error returned when a `Filter` can't be parsed from a query string.
+#[derive(Debug)]
+pub struct ParseFilterError {
+    key: String,
+    value: String,
+}
+
+impl ParseFilterError {
+    fn new<K: Into<String>, V: Into<String>>(key: K, value: V) -> ParseFilterError {
+        ParseFilterError {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+impl StdError for ParseFilterError {}
+
+impl fmt::Display for ParseFilterError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "invalid filter parameter `{}={}`",
+            self.key, self.value
+        )
+    }
+}
+
+impl<E> crate::QueryString for Filter<E> {
     fn to_query_string(&self) -> String {
         let map_filters = |f: &FilterEntry| {
             let value = match f.value {
@@ -480,10 +692,7 @@ impl std::cmp::Eq for FilterEntry {}
 
 impl PartialEq for FilterEntry {
     fn eq(&self, other: &FilterEntry) -> bool {
-        match self.cmp(other) {
-            std::cmp::Ordering::Equal => true,
-            _ => false,
-        }
+        matches!(self.cmp(other), std::cmp::Ordering::Equal)
     }
 }
 
@@ -500,6 +709,7 @@ impl PartialOrd for FilterEntry {
 }
 // }}}
 
+#[derive(Clone)]
 enum Sorting {
     Asc(String),
     Desc(String),
@@ -522,7 +732,12 @@ impl fmt::Display for Sorting {
 /// Filter operators of mod.io.
 ///
 /// See [mod.io docs](https://docs.mod.io/#filtering) for more information.
-#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(
+    feature = "filter-serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Operator {
     /// Equal to (`id=1`)
     Equals,
@@ -548,6 +763,31 @@ pub enum Operator {
     BitwiseAnd,
 }
 
+impl Operator {
+    /// Split a query parameter name into the field name and the operator suffix.
+    fn split(key: &str) -> (&str, Operator) {
+        // Suffixes that end with another suffix must be checked first.
+        const SUFFIXES: &[(&str, Operator)] = &[
+            ("-not-lk", Operator::NotLike),
+            ("-not-in", Operator::NotIn),
+            ("-bitwise-and", Operator::BitwiseAnd),
+            ("-not", Operator::Not),
+            ("-lk", Operator::Like),
+            ("-in", Operator::In),
+            ("-min", Operator::Min),
+            ("-max", Operator::Max),
+            ("-st", Operator::SmallerThan),
+            ("-gt", Operator::GreaterThan),
+        ];
+        for (suffix, op) in SUFFIXES {
+            if let Some(name) = key.strip_suffix(suffix) {
+                return (name, op.clone());
+            }
+        }
+        (key, Operator::Equals)
+    }
+}
+
 impl fmt::Display for Operator {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -600,17 +840,352 @@ impl<T: fmt::Display> From<Vec<T>> for OneOrMany<T> {
     }
 }
 
+/// Types accepted as values of filter fields.
+pub trait FilterValue: fmt::Display {
+    #[doc(hidden)]
+    const KIND: expr::ValueKind;
+}
+
+macro_rules! impl_filter_value {
+    ($kind:ident: $($type:ty),*) => {
+        $(
+            impl FilterValue for $type {
+                const KIND: expr::ValueKind = expr::ValueKind::$kind;
+            }
+        )*
+    };
+}
+
+impl_filter_value!(Number: u32, u64, i8, Timestamp);
+impl_filter_value!(Text: String);
+impl_filter_value!(
+    Number: crate::types::Status,
+    crate::types::game::PresentationOption,
+    crate::types::game::SubmissionOption,
+    crate::types::game::CurationOption,
+    crate::types::game::MaturityOptions,
+    crate::types::game::CommunityOptions,
+    crate::types::game::RevenueOptions,
+    crate::types::game::ApiAccessOptions,
+    crate::types::mods::Visibility,
+    crate::types::mods::MaturityOption,
+    crate::types::mods::VirusStatus,
+    crate::types::mods::VirusPositive,
+    crate::types::mods::TeamLevel
+);
+impl_filter_value!(Text: crate::types::EventType, crate::types::mods::EventType);
+
+/// Conversion into the value or the list of values of a typed filter field.
+///
+/// Besides the value type itself and `Vec`s of it, strings accept `&str` and timestamps accept
+/// `u64` seconds or `SystemTime`.
+pub trait IntoValues<T: FilterValue> {
+    fn into_values(self) -> OneOrMany<T>;
+}
+
+impl<T: FilterValue> IntoValues<T> for T {
+    fn into_values(self) -> OneOrMany<T> {
+        OneOrMany::One(self)
+    }
+}
+
+impl<T: FilterValue> IntoValues<T> for Vec<T> {
+    fn into_values(self) -> OneOrMany<T> {
+        OneOrMany::Many(self)
+    }
+}
+
+macro_rules! impl_into_values {
+    ($target:ty: $($type:ty),*) => {
+        $(
+            impl IntoValues<$target> for $type {
+                fn into_values(self) -> OneOrMany<$target> {
+                    OneOrMany::One(self.into())
+                }
+            }
+
+            impl IntoValues<$target> for Vec<$type> {
+                fn into_values(self) -> OneOrMany<$target> {
+                    OneOrMany::Many(self.into_iter().map(Into::into).collect())
+                }
+            }
+        )*
+    };
+}
+
+impl_into_values!(String: &str, &String);
+impl_into_values!(Timestamp: u64, SystemTime);
+
+/// Unix timestamp in seconds used by the date filters.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Timestamp(pub u64);
+
+impl From<u64> for Timestamp {
+    fn from(secs: u64) -> Timestamp {
+        Timestamp(secs)
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    /// Times before the Unix epoch are clamped to `0`.
+    fn from(time: SystemTime) -> Timestamp {
+        let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        Timestamp(secs)
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(fmt)
+    }
+}
+
+/// Serde support for `Filter` with the `filter-serde` feature.
+///
+/// ```json
+/// {
+///   "filters": [
+///     { "field": "id", "op": "in", "value": ["1", "2"] },
+///     { "field": "name", "op": "like", "value": "foo*" }
+///   ],
+///   "sort": { "field": "date_added", "order": "desc" },
+///   "limit": 10,
+///   "offset": 20
+/// }
+/// ```
+///
+/// `op` defaults to `equals` and `order` to `asc`. Numbers and booleans are accepted as values.
+#[cfg(feature = "filter-serde")]
+mod serde_impl {
+    use std::fmt;
+
+    use serde::de::{Deserialize, Deserializer};
+    use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+    use super::{Filter, FilterEntry, OneOrMany, Operator, Sorting};
+
+    impl<E> Serialize for Filter<E> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut s = serializer.serialize_struct("Filter", 4)?;
+            s.serialize_field("filters", &self.filters)?;
+            match self.order_by {
+                Some(ref sort) => s.serialize_field("sort", sort)?,
+                None => s.skip_field("sort")?,
+            }
+            match self.limit {
+                Some(ref limit) => s.serialize_field("limit", limit)?,
+                None => s.skip_field("limit")?,
+            }
+            match self.offset {
+                Some(ref offset) => s.serialize_field("offset", offset)?,
+                None => s.skip_field("offset")?,
+            }
+            s.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Filter {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Filter, D::Error> {
+            #[derive(Deserialize)]
+            #[serde(deny_unknown_fields)]
+            struct Repr {
+                #[serde(default)]
+                filters: Vec<FilterEntry>,
+                sort: Option<Sorting>,
+                limit: Option<usize>,
+                offset: Option<usize>,
+            }
+
+            let repr = Repr::deserialize(deserializer)?;
+            let mut filter = Filter {
+                order_by: repr.sort,
+                limit: repr.limit,
+                offset: repr.offset,
+                ..Default::default()
+            };
+            for entry in repr.filters {
+                filter.filters.replace(entry);
+            }
+            Ok(filter)
+        }
+    }
+
+    impl Serialize for FilterEntry {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut s = serializer.serialize_struct("FilterEntry", 3)?;
+            s.serialize_field("field", &self.name)?;
+            s.serialize_field("op", &self.op)?;
+            s.serialize_field("value", &self.value)?;
+            s.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for FilterEntry {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<FilterEntry, D::Error> {
+            #[derive(Deserialize)]
+            #[serde(deny_unknown_fields)]
+            struct Repr {
+                field: String,
+                #[serde(default = "equals")]
+                op: Operator,
+                value: OneOrMany<Scalar>,
+            }
+
+            fn equals() -> Operator {
+                Operator::Equals
+            }
+
+            let repr = Repr::deserialize(deserializer)?;
+            Ok(FilterEntry::new(
+                repr.field,
+                repr.op,
+                repr.value.to_string(),
+            ))
+        }
+    }
+
+    /// Filter values are stored as strings but numbers and booleans are accepted too.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Scalar {
+        Bool(bool),
+        Unsigned(u64),
+        Signed(i64),
+        Float(f64),
+        String(String),
+    }
+
+    impl fmt::Display for Scalar {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Scalar::Bool(v) => v.fmt(f),
+                Scalar::Unsigned(v) => v.fmt(f),
+                Scalar::Signed(v) => v.fmt(f),
+                Scalar::Float(v) => v.fmt(f),
+                Scalar::String(v) => v.fmt(f),
+            }
+        }
+    }
+
+    #[derive(Default, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+        #[default]
+        Asc,
+        Desc,
+    }
+
+    impl Serialize for Sorting {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let (field, order) = match self {
+                Sorting::Asc(field) => (field, Order::Asc),
+                Sorting::Desc(field) => (field, Order::Desc),
+            };
+            let mut s = serializer.serialize_struct("Sorting", 2)?;
+            s.serialize_field("field", field)?;
+            s.serialize_field("order", &order)?;
+            s.end()
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Sorting {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Sorting, D::Error> {
+            #[derive(Deserialize)]
+            #[serde(deny_unknown_fields)]
+            struct Repr {
+                field: String,
+                #[serde(default)]
+                order: Order,
+            }
+
+            let repr = Repr::deserialize(deserializer)?;
+            Ok(match repr.order {
+                Order::Asc => Sorting::Asc(repr.field),
+                Order::Desc => Sorting::Desc(repr.field),
+            })
+        }
+    }
+
+    impl<T> Serialize for OneOrMany<T>
+    where
+        T: fmt::Display + Serialize,
+    {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                OneOrMany::One(v) => v.serialize(serializer),
+                OneOrMany::Many(v) => v.serialize(serializer),
+            }
+        }
+    }
+
+    impl<'de, T> Deserialize<'de> for OneOrMany<T>
+    where
+        T: fmt::Display + Deserialize<'de>,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<OneOrMany<T>, D::Error> {
+            #[derive(Deserialize)]
+            #[serde(untagged)]
+            enum Repr<T> {
+                Many(Vec<T>),
+                One(T),
+            }
+
+            Ok(match Repr::deserialize(deserializer)? {
+                Repr::Many(v) => OneOrMany::Many(v),
+                Repr::One(v) => OneOrMany::One(v),
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::filter::prelude::*;
+        use crate::QueryString;
+
+        #[test]
+        fn json() {
+            let filter = Id::_in(vec![1, 2])
+                .and(Name::like("foo*"))
+                .order_by(DateAdded::desc())
+                .limit(10)
+                .offset(20);
+            let json = serde_json::to_string(&filter).unwrap();
+            assert_eq!(
+                json,
+                r#"{"filters":[{"field":"id","op":"in","value":["1","2"]},{"field":"name","op":"like","value":"foo*"}],"sort":{"field":"date_added","order":"desc"},"limit":10,"offset":20}"#
+            );
+            let parsed: Filter = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed.to_query_string(), filter.to_query_string());
+
+            let json = r#"{"filters":[{"field":"id","value":5},{"field":"visible","op":"not","value":false}],"sort":{"field":"name"}}"#;
+            let parsed: Filter = serde_json::from_str(json).unwrap();
+            assert_eq!(
+                parsed.to_query_string(),
+                "id=5&visible-not=false&_sort=name"
+            );
+
+            assert!(serde_json::from_str::<Filter>(r#"{"limit":1,"foo":1}"#).is_err());
+            assert_eq!(
+                serde_json::to_string(&Filter::default()).unwrap(),
+                r#"{"filters":[]}"#
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    type Endpoint = super::endpoint::Any;
+
     #[test]
     #[allow(dead_code)]
     fn filters() {
         use super::prelude::*;
         use crate::QueryString;
 
-        filter!(GameId, GAME_ID, "game_id", Eq, NotEq, Like, In, Cmp, OrderBy);
-        filter!(NameId, NAME_ID, "name_id", Eq, NotEq, Like, In, Cmp, OrderBy);
-        filter!(BitOption, BIT_OPTION, "bit_option", Bit);
+        filter!(GameId, GAME_ID, "game_id", u32: Eq, NotEq, Like, In, Cmp, OrderBy);
+        filter!(NameId, NAME_ID, "name_id", String: Eq, NotEq, Like, In, Cmp, OrderBy);
+        filter!(BitOption, BIT_OPTION, "bit_option", u32: Bit);
 
         assert_eq!(GAME_ID, "game_id");
         assert_eq!(NAME_ID, "name_id");
@@ -630,10 +1205,10 @@ mod test {
         let f = NameId::like("foo*").and(NameId::not_like("bar*"));
         assert_eq!(f.to_query_string(), "name_id-lk=foo*&name_id-not-lk=bar*");
 
-        let f = NameId::gt(1).and(NameId::lt(2));
+        let f = NameId::gt("1").and(NameId::lt("2"));
         assert_eq!(f.to_query_string(), "name_id-st=2&name_id-gt=1");
 
-        let f = NameId::ge(1).and(NameId::le(2));
+        let f = NameId::ge("1").and(NameId::le("2"));
         assert_eq!(f.to_query_string(), "name_id-min=1&name_id-max=2");
 
         let f = BitOption::bit_and(1);
@@ -642,6 +1217,13 @@ mod test {
         let f = NameId::desc();
         assert_eq!(f.to_query_string(), "_sort=-name_id");
 
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(10);
+        let f = DateAdded::gt(time).and(DateAdded::_in(vec![20, 30]));
+        assert_eq!(
+            f.to_query_string(),
+            "date_added-in=20%2C30&date_added-gt=10"
+        );
+
         let f = with_limit(10).and(with_limit(20));
         assert_eq!(f.to_query_string(), "_limit=20");
 
@@ -650,12 +1232,13 @@ mod test {
     }
 
     #[test]
+    #[allow(dead_code)]
     fn custom_filters() {
         use super::prelude::*;
         use super::*;
         use crate::QueryString;
 
-        filter!(GameId, GAME_ID, "game_id", Eq);
+        filter!(GameId, GAME_ID, "game_id", u32: Eq);
 
         let f = GameId::eq(1).and(custom_filter("foo", Operator::Equals, "bar"));
         assert_eq!(f.to_query_string(), "foo=bar&game_id=1");
@@ -663,6 +1246,85 @@ mod test {
         let f = custom_order_by_asc("foo");
         assert_eq!(f.to_query_string(), "_sort=foo");
     }
+
+    #[test]
+    fn metadata_kvp() {
+        use crate::mods::filters::MetadataKVP;
+        use crate::QueryString;
+
+        let f = MetadataKVP::key("difficulty").eq("hard").unwrap();
+        assert_eq!(f.to_query_string(), "metadata_kvp=difficulty%3Ahard");
+
+        let f = MetadataKVP::key("difficulty").like("h*").unwrap();
+        assert_eq!(f.to_query_string(), "metadata_kvp-lk=difficulty%3Ah*");
+
+        let f = MetadataKVP::key("difficulty")
+            ._in(vec!["easy", "hard"])
+            .unwrap();
+        assert_eq!(
+            f.to_query_string(),
+            "metadata_kvp-in=difficulty%3Aeasy%2Cdifficulty%3Ahard"
+        );
+
+        let f = MetadataKVP::key("url").eq("https://example.com").unwrap();
+        assert_eq!(
+            f.to_query_string(),
+            "metadata_kvp=url%3Ahttps%3A%2F%2Fexample.com"
+        );
+
+        let key = MetadataKVP::key("difficulty");
+        assert!(matches!(key.eq("h*"), Err(ref e) if e.is_validation()));
+        assert!(key.ne("h*").is_err());
+        assert!(key.eq("easy,hard").is_err());
+        assert!(key.like("easy,hard").is_err());
+        assert!(key._in(vec!["easy", "easy,hard"]).is_err());
+        assert!(key.not_in(vec!["h*"]).is_err());
+        assert!(MetadataKVP::key("a:b").eq("c").is_err());
+        assert!(MetadataKVP::key("a,b").eq("c").is_err());
+        assert!(MetadataKVP::key("a*").like("c").is_err());
+    }
+
+    #[test]
+    #[allow(dead_code)]
+    fn parse_query_string() {
+        use super::prelude::*;
+        use crate::QueryString;
+
+        filter!(GameId, GAME_ID, "game_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(NameId, NAME_ID, "name_id", String: Like);
+        filter!(BitOption, BIT_OPTION, "bit_option", u32: Bit);
+
+        let filters = vec![
+            GameId::eq(1),
+            GameId::ne(1).and(GameId::_in(vec![1, 2])),
+            GameId::not_in(vec![3]).and(NameId::like("foo*")),
+            NameId::not_like("bar*")
+                .and(GameId::ge(1))
+                .and(GameId::le(5)),
+            GameId::gt(1).and(GameId::lt(5)),
+            BitOption::bit_and(3).and(Fulltext::eq("foo bar&baz")),
+            GameId::eq(1).order_by(GameId::desc()).limit(10).offset(20),
+            GameId::asc(),
+        ];
+        for f in filters {
+            let query = f.to_query_string();
+            let parsed = query.parse::<Filter>().unwrap();
+            assert_eq!(parsed.to_query_string(), query);
+        }
+
+        let f = Filter::from_query_string("?name-not-lk=foo*&id-in=1,2").unwrap();
+        assert_eq!(f.to_query_string(), "id-in=1%2C2&name-not-lk=foo*");
+
+        // Values with commas are split into separate values.
+        let f = Filter::from_query_string(&Name::_in(vec!["a,b", "c"]).to_query_string()).unwrap();
+        let entry = f.filters.iter().next().unwrap();
+        assert!(matches!(entry.value, OneOrMany::Many(ref v) if v == &["a", "b", "c"]));
+
+        assert!("_limit=ten".parse::<Filter>().is_err());
+        assert!("_sort=".parse::<Filter>().is_err());
+        assert!("_sort=-".parse::<Filter>().is_err());
+        assert!("-in=1".parse::<Filter>().is_err());
+    }
 }
 
 // vim: fdm=marker
@@ -0,0 +1,408 @@
+//! OR-queries merged from multiple requests
+//!
+//! The filters of mod.io are always combined with `AND`. [`Filter::any`] creates an [`AnyOf`]
+//! that requests one stream per alternative and merges the results. Entities returned by more
+//! than one alternative are only yielded once, compared by their `id`.
+//!
+//! The sorting, limit and offset of `AnyOf` apply to the merged results. Every alternative is
+//! requested with the same sorting, so numeric sort fields are merged lazily and only as many
+//! pages as needed are requested. Text fields are sorted by mod.io with a collation that can
+//! differ from the local comparison, the results are collected and sorted locally instead.
+//!
+//! With a limit, every alternative is capped at `offset + limit` entities because an entity of
+//! the merged page is also on the same page of its own alternative. Without a limit, sorting by
+//! a text field requests every alternative to its last page before the first entity is yielded.
+//!
+//! ```no_run
+//! use modio::filter::prelude::*;
+//! use modio::mods::filters::Tags;
+//! # use modio::{Credentials, Modio};
+//! # let modio = Modio::new(Credentials::ApiKey(String::new()))?;
+//!
+//! let filter = Filter::any(vec![Tags::eq("Map"), Tags::eq("Texture")])
+//!     .order_by(Id::desc())
+//!     .limit(20);
+//! let mods = modio.game(51).mods().iter_any(&filter);
+//! # Ok::<_, modio::Error>(())
+//! ```
+use std::collections::{HashSet, VecDeque};
+use std::mem;
+
+use futures::{Async, Poll, Stream as StdStream};
+
+use super::endpoint;
+use super::matches::{compare, Entity, Value};
+use super::{Filter, Sorting};
+use crate::{Error, QueryString, Stream};
+
+/// Alternatives of filters, created with [`Filter::any`].
+pub struct AnyOf<E = endpoint::Any> {
+    filters: Vec<Filter<E>>,
+    order_by: Option<Sorting>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+}
+
+impl<E> Filter<E> {
+    /// Match the entities that pass any of the filters.
+    ///
+    /// The sorting, limit and offset of the filters are ignored, use the methods of `AnyOf`
+    /// instead.
+    ///
+    /// ```
+    /// use modio::filter::prelude::*;
+    /// use modio::mods::filters::{Tags, Visible};
+    /// use modio::mods::Visibility;
+    ///
+    /// let filter = Filter::any(vec![
+    ///     Tags::eq("Map").and(Visible::eq(Visibility::Public)),
+    ///     Tags::eq("Texture").and(SubmittedBy::eq(1)),
+    /// ])
+    /// .order_by(DateAdded::desc())
+    /// .limit(50);
+    /// ```
+    pub fn any(filters: Vec<Filter<E>>) -> AnyOf<E> {
+        let filters = filters
+            .into_iter()
+            .map(|f| Filter {
+                order_by: None,
+                limit: None,
+                offset: None,
+                ..f
+            })
+            .collect();
+        AnyOf {
+            filters,
+            order_by: None,
+            limit: None,
+            offset: None,
+        }
+    }
+}
+
+impl<E> AnyOf<E> {
+    pub fn order_by<E2>(self, other: Filter<E2>) -> AnyOf<E::Output>
+    where
+        E: endpoint::Merge<E2>,
+    {
+        let order_by = other.order_by.or(self.order_by);
+        let filters = self
+            .filters
+            .into_iter()
+            .map(|f| Filter {
+                order_by: order_by.clone(),
+                ..f.cast()
+            })
+            .collect();
+        AnyOf {
+            filters,
+            order_by,
+            limit: self.limit,
+            offset: self.offset,
+        }
+    }
+
+    pub fn limit(self, limit: usize) -> AnyOf<E> {
+        AnyOf {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    pub fn offset(self, offset: usize) -> AnyOf<E> {
+        AnyOf {
+            offset: Some(offset),
+            ..self
+        }
+    }
+
+    /// The query strings of the alternatives.
+    pub(crate) fn queries(&self) -> impl Iterator<Item = String> + '_ {
+        self.filters.iter().map(QueryString::to_query_string)
+    }
+
+    /// Merge the streams of the alternatives in the order of `queries`.
+    pub(crate) fn merge<D>(&self, streams: Vec<Stream<D>>) -> Stream<D>
+    where
+        D: Entity + Send + 'static,
+    {
+        let order_by = self.order_by.as_ref().map(|s| match s {
+            Sorting::Asc(name) => (name.clone(), false),
+            Sorting::Desc(name) => (name.clone(), true),
+        });
+        let streams = match self.limit {
+            Some(limit) => {
+                let n = self.offset.unwrap_or(0).saturating_add(limit) as u64;
+                streams
+                    .into_iter()
+                    .map(|s| Box::new(s.take(n)) as Stream<D>)
+                    .collect()
+            }
+            None => streams,
+        };
+        let heads = streams.iter().map(|_| None).collect::<Vec<_>>();
+        Box::new(Merge {
+            streams: streams.into_iter().map(Some).collect(),
+            heads,
+            order_by,
+            mode: Mode::Pending,
+            seen: HashSet::new(),
+            offset: self.offset.unwrap_or(0),
+            remaining: self.limit.unwrap_or(usize::MAX),
+        })
+    }
+}
+
+enum Mode<D> {
+    /// Waiting for the first item of every stream.
+    Pending,
+    /// Take the smallest head of the streams.
+    Merge,
+    /// Collect all items to sort them locally.
+    Collect(Vec<D>),
+    Drain(VecDeque<D>),
+}
+
+struct Merge<D> {
+    streams: Vec<Option<Stream<D>>>,
+    heads: Vec<Option<D>>,
+    order_by: Option<(String, bool)>,
+    mode: Mode<D>,
+    seen: HashSet<u64>,
+    offset: usize,
+    remaining: usize,
+}
+
+impl<D: Entity> Merge<D> {
+    /// Poll the streams without a head. Returns `false` if a stream isn't ready.
+    fn fill(&mut self) -> Result<bool, Error> {
+        let mut ready = true;
+        for (stream, head) in self.streams.iter_mut().zip(self.heads.iter_mut()) {
+            if head.is_some() {
+                continue;
+            }
+            if let Some(s) = stream {
+                match s.poll()? {
+                    Async::Ready(Some(item)) => *head = Some(item),
+                    Async::Ready(None) => *stream = None,
+                    Async::NotReady => ready = false,
+                }
+            }
+        }
+        Ok(ready)
+    }
+
+    fn next_item(&mut self) -> Poll<Option<D>, Error> {
+        let ready = self.fill()?;
+        let (name, desc) = match self.order_by {
+            Some((ref name, desc)) => (name.clone(), desc),
+            None => {
+                // Without sorting any head can be taken.
+                if let Some(item) = self.heads.iter_mut().find_map(Option::take) {
+                    return Ok(Async::Ready(Some(item)));
+                }
+                return Ok(if ready {
+                    Async::Ready(None)
+                } else {
+                    Async::NotReady
+                });
+            }
+        };
+        if let Mode::Pending = self.mode {
+            if !ready {
+                return Ok(Async::NotReady);
+            }
+            let numeric = self
+                .heads
+                .iter()
+                .flatten()
+                .all(|h| matches!(h.field(&name), Some(Value::Number(_))));
+            self.mode = if numeric {
+                Mode::Merge
+            } else {
+                Mode::Collect(Vec::new())
+            };
+        }
+        match self.mode {
+            Mode::Pending => unreachable!(),
+            Mode::Merge => {
+                if !ready {
+                    return Ok(Async::NotReady);
+                }
+                let mut next: Option<usize> = None;
+                for (i, head) in self.heads.iter().enumerate() {
+                    if let Some(head) = head {
+                        let smaller = match next.and_then(|n| self.heads[n].as_ref()) {
+                            Some(min) => order(head, min, &name, desc) == std::cmp::Ordering::Less,
+                            None => true,
+                        };
+                        if smaller {
+                            next = Some(i);
+                        }
+                    }
+                }
+                Ok(Async::Ready(next.and_then(|n| self.heads[n].take())))
+            }
+            Mode::Collect(_) => loop {
+                let heads = self.heads.iter_mut().filter_map(Option::take);
+                if let Mode::Collect(ref mut items) = self.mode {
+                    items.extend(heads);
+                }
+                if self.streams.iter().all(Option::is_none) {
+                    if let Mode::Collect(mut items) = mem::replace(&mut self.mode, Mode::Merge) {
+                        items.sort_by(|a, b| order(a, b, &name, desc));
+                        self.mode = Mode::Drain(items.into());
+                    }
+                    return Ok(Async::Ready(self.pop()));
+                }
+                if !self.fill()? {
+                    return Ok(Async::NotReady);
+                }
+            },
+            Mode::Drain(_) => Ok(Async::Ready(self.pop())),
+        }
+    }
+
+    fn pop(&mut self) -> Option<D> {
+        match self.mode {
+            Mode::Drain(ref mut items) => items.pop_front(),
+            _ => None,
+        }
+    }
+}
+
+fn order<D: Entity>(a: &D, b: &D, name: &str, desc: bool) -> std::cmp::Ordering {
+    let ord = match (a.field(name), b.field(name)) {
+        (Some(a), Some(b)) => compare(&a, &b),
+        _ => std::cmp::Ordering::Equal,
+    };
+    if desc {
+        ord.reverse()
+    } else {
+        ord
+    }
+}
+
+impl<D: Entity> StdStream for Merge<D> {
+    type Item = D;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<D>, Error> {
+        loop {
+            if self.remaining == 0 {
+                return Ok(Async::Ready(None));
+            }
+            let item = match self.next_item()? {
+                Async::Ready(Some(item)) => item,
+                Async::Ready(None) => return Ok(Async::Ready(None)),
+                Async::NotReady => return Ok(Async::NotReady),
+            };
+            if let Some(Value::Number(id)) = item.field("id") {
+                if !self.seen.insert(id as u64) {
+                    continue;
+                }
+            }
+            if self.offset > 0 {
+                self.offset -= 1;
+                continue;
+            }
+            self.remaining -= 1;
+            return Ok(Async::Ready(Some(item)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{stream, Future};
+
+    use super::*;
+    use crate::filter::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Item(u32, &'static str);
+
+    impl Entity for Item {
+        type Endpoint = endpoint::Any;
+
+        fn field(&self, name: &str) -> Option<Value<'_>> {
+            match name {
+                "id" => Some(self.0.into()),
+                "name" => Some(self.1.into()),
+                _ => None,
+            }
+        }
+    }
+
+    fn merge(filter: &AnyOf, items: Vec<Vec<Item>>) -> Vec<u32> {
+        let streams = items
+            .into_iter()
+            .map(|items| Box::new(stream::iter_ok(items)) as Stream<Item>)
+            .collect();
+        let items = filter.merge(streams).collect().wait().unwrap();
+        items.into_iter().map(|i| i.0).collect()
+    }
+
+    fn items() -> Vec<Vec<Item>> {
+        vec![
+            vec![Item(1, "b"), Item(4, "D"), Item(6, "f")],
+            vec![Item(2, "a"), Item(4, "D"), Item(5, "c")],
+        ]
+    }
+
+    #[test]
+    fn merge_sorted() {
+        let filter = Filter::any(vec![Id::gt(0), Id::lt(10)]).order_by(Id::asc());
+        assert_eq!(merge(&filter, items()), [1, 2, 4, 5, 6]);
+
+        let filter = Filter::any(vec![Id::gt(0)])
+            .order_by(Id::asc())
+            .offset(1)
+            .limit(2);
+        assert_eq!(merge(&filter, items()), [2, 4]);
+
+        let filter = Filter::any(vec![Id::gt(0)]).order_by(Name::asc());
+        assert_eq!(merge(&filter, items()), [2, 1, 5, 4, 6]);
+    }
+
+    #[test]
+    fn limit_alternatives() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // Count the items pulled from the alternatives.
+        let pulled = Arc::new(AtomicUsize::new(0));
+        let streams = items()
+            .into_iter()
+            .map(|items| {
+                let pulled = pulled.clone();
+                let s = stream::iter_ok(items).inspect(move |_| {
+                    pulled.fetch_add(1, Ordering::SeqCst);
+                });
+                Box::new(s) as Stream<Item>
+            })
+            .collect();
+        let filter = Filter::any(vec![Id::gt(0), Id::lt(10)])
+            .order_by(Name::asc())
+            .limit(1)
+            .offset(1);
+        let items = filter.merge(streams).collect().wait().unwrap();
+        assert_eq!(items.iter().map(|i| i.0).collect::<Vec<_>>(), [1]);
+        assert_eq!(pulled.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn merge_unsorted() {
+        let filter = Filter::any(vec![Id::gt(0), Id::lt(10)]);
+        let mut ids = merge(&filter, items());
+        ids.sort();
+        assert_eq!(ids, [1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn queries() {
+        let filter = Filter::any(vec![Id::eq(1).limit(5), Name::eq("foo")]).order_by(Id::desc());
+        let queries = filter.queries().collect::<Vec<_>>();
+        assert_eq!(queries, ["id=1&_sort=-id", "name=foo&_sort=-id"]);
+    }
+}
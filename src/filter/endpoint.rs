@@ -0,0 +1,85 @@
+//! Endpoint markers for `Filter`
+//!
+//! The marker of a `Filter` is taken from the filters it was created with. Filters of the
+//! [`prelude`](../prelude/index.html) and custom filters are marked with [`Any`] and can be
+//! combined with the filters of every endpoint.
+//!
+//! ```
+//! use modio::filter::prelude::*;
+//! use modio::filter::endpoint;
+//! use modio::mods::filters::Tags;
+//!
+//! let filter: Filter<endpoint::Mods> = Id::gt(10).and(Tags::eq("Map")).order_by(Id::desc());
+//! ```
+
+/// Marker of the shared filters usable for every endpoint.
+#[derive(Clone, Copy, Debug)]
+pub enum Any {}
+
+/// A marker of a concrete endpoint.
+pub trait Endpoint: crate::private::Sealed {}
+
+/// Determines the endpoint marker of two combined filters.
+pub trait Merge<E> {
+    type Output;
+}
+
+impl Merge<Any> for Any {
+    type Output = Any;
+}
+
+impl<E: Endpoint> Merge<E> for Any {
+    type Output = E;
+}
+
+impl<E: Endpoint> Merge<Any> for E {
+    type Output = E;
+}
+
+impl<E: Endpoint> Merge<E> for E {
+    type Output = E;
+}
+
+/// Filters with this marker can be used for the endpoint `E`.
+///
+/// Implemented for the marker of `E` itself and for [`Any`].
+pub trait Scope<E>: Merge<E, Output = E> {}
+
+impl<S: Merge<E, Output = E>, E> Scope<E> for S {}
+
+macro_rules! endpoints {
+    ($($(#[$outer:meta])* $name:ident,)*) => {
+        $(
+            $(#[$outer])*
+            #[derive(Clone, Copy, Debug)]
+            pub enum $name {}
+
+            impl crate::private::Sealed for $name {}
+            impl Endpoint for $name {}
+        )*
+    };
+}
+
+endpoints! {
+    /// [`games::filters`](../../games/filters/index.html)
+    Games,
+    /// [`mods::filters`](../../mods/filters/index.html), also used for the subscriptions of the
+    /// authenticated user.
+    Mods,
+    /// [`mods::filters::events`](../../mods/filters/events/index.html)
+    ModEvents,
+    /// [`mods::filters::stats`](../../mods/filters/stats/index.html)
+    ModStats,
+    /// [`files::filters`](../../files/filters/index.html)
+    Files,
+    /// [`comments::filters`](../../comments/filters/index.html)
+    Comments,
+    /// [`users::filters`](../../users/filters/index.html)
+    Users,
+    /// [`teams::filters`](../../teams/filters/index.html)
+    Teams,
+    /// [`me::filters::events`](../../me/filters/events/index.html)
+    UserEvents,
+    /// [`me::filters::ratings`](../../me/filters/ratings/index.html)
+    Ratings,
+}
@@ -0,0 +1,621 @@
+//! Filter expressions
+//!
+//! A small query language that compiles into a [`Filter`](../struct.Filter.html). The field
+//! names, operators and values of an expression are checked against the [`Schema`] of the
+//! endpoint, e.g. [`mods::filters::SCHEMA`](../../mods/filters/constant.SCHEMA.html).
+//!
+//! ```
+//! use modio::filter::Filter;
+//! use modio::mods::filters::SCHEMA;
+//!
+//! let expr = r#"name ~ "*zombie*" and tags = "Map" order by -popular limit 50"#;
+//! let filter = Filter::from_expr(expr, &SCHEMA)?;
+//!
+//! // Mods can be sorted by `downloads` but not filtered.
+//! let expr = r#"name ~ "*zombie*" and downloads > 1000"#;
+//! let err = Filter::from_expr(expr, &SCHEMA).err().unwrap();
+//! assert_eq!(&expr[err.span()], ">");
+//! # Ok::<_, modio::filter::expr::ParseError>(())
+//! ```
+//!
+//! An expression is a list of conditions joined by `and`, optionally followed by
+//! `order by [-]field`, `limit n` and `offset n`.
+//!
+//! | Expression            | Operator                                                |
+//! |-----------------------|---------------------------------------------------------|
+//! | `=`, `!=`             | `Equals`, `Not`                                         |
+//! | `~`, `!~`             | `Like`, `NotLike`                                       |
+//! | `in (a, b)`           | `In`                                                    |
+//! | `not in (a, b)`       | `NotIn`                                                 |
+//! | `>=`, `<=`, `>`, `<`  | `Min`, `Max`, `GreaterThan`, `SmallerThan`              |
+//! | `&`                   | `BitwiseAnd`                                            |
+//!
+//! Strings are enclosed in double quotes and support the `\"` and `\\` escapes. Keywords are
+//! case-insensitive.
+use std::error::Error as StdError;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::Range;
+
+use super::endpoint::Any;
+use super::{Filter, OneOrMany, Operator, Sorting};
+
+/// The kind of values accepted by a filter field.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValueKind {
+    /// Integers or decimal numbers.
+    Number,
+    /// Quoted strings.
+    Text,
+    /// Numbers or quoted strings.
+    Any,
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueKind::Number => "number",
+            ValueKind::Text => "string",
+            ValueKind::Any => "number or string",
+        }
+        .fmt(fmt)
+    }
+}
+
+/// The operator groups of the `filter!` macro.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Capability {
+    Eq,
+    NotEq,
+    Like,
+    In,
+    Cmp,
+    Bit,
+    OrderBy,
+}
+
+/// Describes a filter field of an endpoint.
+#[derive(Clone, Copy, Debug)]
+pub struct Field {
+    name: &'static str,
+    caps: &'static [Capability],
+    kind: ValueKind,
+}
+
+impl Field {
+    #[doc(hidden)]
+    pub const fn new(name: &'static str, kind: ValueKind, caps: &'static [Capability]) -> Field {
+        Field { name, caps, kind }
+    }
+
+    /// Name of the field as used by mod.io.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The kind of values accepted by the field.
+    pub fn kind(&self) -> ValueKind {
+        self.kind
+    }
+
+    /// Returns `true` if the field can be filtered with the operator.
+    pub fn supports(&self, op: &Operator) -> bool {
+        let cap = match op {
+            Operator::Equals => Capability::Eq,
+            Operator::Not => Capability::NotEq,
+            Operator::Like | Operator::NotLike => Capability::Like,
+            Operator::In | Operator::NotIn => Capability::In,
+            Operator::Min | Operator::Max | Operator::SmallerThan | Operator::GreaterThan => {
+                Capability::Cmp
+            }
+            Operator::BitwiseAnd => Capability::Bit,
+        };
+        self.caps.contains(&cap)
+    }
+
+    /// Returns `true` if the results can be sorted by the field.
+    pub fn sortable(&self) -> bool {
+        self.caps.contains(&Capability::OrderBy)
+    }
+}
+
+/// The filter fields of the endpoint `E`.
+#[derive(Clone, Copy, Debug)]
+pub struct Schema<E = Any> {
+    fields: &'static [Field],
+    endpoint: PhantomData<E>,
+}
+
+impl<E> Schema<E> {
+    #[doc(hidden)]
+    pub const fn new(fields: &'static [Field]) -> Schema<E> {
+        Schema {
+            fields,
+            endpoint: PhantomData,
+        }
+    }
+
+    pub fn fields(&self) -> &'static [Field] {
+        self.fields
+    }
+
+    /// Look up a field by its name.
+    pub fn field(&self, name: &str) -> Option<&'static Field> {
+        find(self.fields, name)
+    }
+}
+
+fn find(fields: &'static [Field], name: &str) -> Option<&'static Field> {
+    fields.iter().find(|f| f.name == name)
+}
+
+/// The error returned when an expression can't be compiled into a `Filter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    span: Range<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnterminatedString,
+    /// The token doesn't fit; `expected` describes what the parser was looking for.
+    UnexpectedToken {
+        expected: &'static str,
+    },
+    UnexpectedEnd {
+        expected: &'static str,
+    },
+    /// `limit` and `offset` require a non-negative integer.
+    InvalidNumber,
+    UnknownField(String),
+    UnsupportedOperator {
+        field: String,
+        op: Operator,
+    },
+    InvalidValue {
+        field: String,
+        expected: ValueKind,
+    },
+    NotSortable(String),
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, span: Range<usize>) -> ParseError {
+        ParseError { kind, span }
+    }
+
+    pub fn kind(&self) -> &ParseErrorKind {
+        &self.kind
+    }
+
+    /// Byte range of the offending token in the expression.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl StdError for ParseError {}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(fmt, "unexpected character `{}`", c)?,
+            ParseErrorKind::UnterminatedString => fmt.write_str("unterminated string")?,
+            ParseErrorKind::UnexpectedToken { expected } => write!(fmt, "expected {}", expected)?,
+            ParseErrorKind::UnexpectedEnd { expected } => {
+                write!(fmt, "unexpected end of expression, expected {}", expected)?
+            }
+            ParseErrorKind::InvalidNumber => fmt.write_str("expected a non-negative integer")?,
+            ParseErrorKind::UnknownField(ref name) => write!(fmt, "unknown field `{}`", name)?,
+            ParseErrorKind::UnsupportedOperator { ref field, ref op } => {
+                write!(fmt, "operator {:?} is not supported by `{}`", op, field)?
+            }
+            ParseErrorKind::InvalidValue {
+                ref field,
+                expected,
+            } => write!(fmt, "`{}` expects a {}", field, expected)?,
+            ParseErrorKind::NotSortable(ref name) => {
+                write!(fmt, "results can't be sorted by `{}`", name)?
+            }
+        }
+        write!(fmt, " at {}..{}", self.span.start, self.span.end)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Number(String),
+    Eq,
+    Ne,
+    Like,
+    NotLike,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Amp,
+    Minus,
+    Comma,
+    LParen,
+    RParen,
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        match self {
+            Token::Ident(s) => s.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, Range<usize>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let mut next_is = |expected: char, end: &mut usize| match chars.peek() {
+            Some(&(i, c)) if c == expected => {
+                chars.next();
+                *end = i + 1;
+                true
+            }
+            _ => false,
+        };
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '=' => Token::Eq,
+            '~' => Token::Like,
+            '&' => Token::Amp,
+            ',' => Token::Comma,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '!' if next_is('=', &mut end) => Token::Ne,
+            '!' if next_is('~', &mut end) => Token::NotLike,
+            '>' if next_is('=', &mut end) => Token::Ge,
+            '>' => Token::Gt,
+            '<' if next_is('=', &mut end) => Token::Le,
+            '<' => Token::Lt,
+            '"' => {
+                let mut value = String::new();
+                let mut escaped = false;
+                let mut closed = false;
+                for (i, c) in chars.by_ref() {
+                    end = i + c.len_utf8();
+                    match c {
+                        c if escaped => {
+                            value.push(c);
+                            escaped = false;
+                        }
+                        '\\' => escaped = true,
+                        '"' => {
+                            closed = true;
+                            break;
+                        }
+                        c => value.push(c),
+                    }
+                }
+                if !closed {
+                    let kind = ParseErrorKind::UnterminatedString;
+                    return Err(ParseError::new(kind, start..input.len()));
+                }
+                Token::Str(value)
+            }
+            '-' | '0'..='9' => {
+                let mut number = c != '-';
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_digit() || (number && c == '.')) {
+                        break;
+                    }
+                    number = true;
+                    chars.next();
+                    end = i + 1;
+                }
+                if number {
+                    Token::Number(input[start..end].to_owned())
+                } else {
+                    Token::Minus
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    chars.next();
+                    end = i + 1;
+                }
+                Token::Ident(input[start..end].to_owned())
+            }
+            c => {
+                let kind = ParseErrorKind::UnexpectedChar(c);
+                return Err(ParseError::new(kind, start..end));
+            }
+        };
+        tokens.push((token, start..end));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, Range<usize>)>,
+    pos: usize,
+    len: usize,
+    fields: &'static [Field],
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<(Token, Range<usize>), ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(token) => {
+                self.pos += 1;
+                Ok(token.clone())
+            }
+            None => Err(self.unexpected(expected)),
+        }
+    }
+
+    /// Error for the current token or the end of the expression.
+    fn unexpected(&self, expected: &'static str) -> ParseError {
+        match self.tokens.get(self.pos) {
+            Some((_, span)) => unexpected(expected, span.clone()),
+            None => ParseError::new(
+                ParseErrorKind::UnexpectedEnd { expected },
+                self.len..self.len,
+            ),
+        }
+    }
+
+    fn eat(&mut self, keyword: &str) -> bool {
+        if self.peek().is_some_and(|t| t.is_keyword(keyword)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), ParseError> {
+        match self.next(expected)? {
+            (ref t, _) if *t == token => Ok(()),
+            (_, span) => Err(unexpected(expected, span)),
+        }
+    }
+
+    fn parse(&mut self) -> Result<Filter, ParseError> {
+        let mut filter = Filter::default();
+        let clause = |t: &Token| ["order", "limit", "offset"].iter().any(|k| t.is_keyword(k));
+        if self.peek().is_some_and(|t| !clause(t)) {
+            loop {
+                filter = filter.and(self.condition()?);
+                if !self.eat("and") {
+                    break;
+                }
+            }
+        }
+        if self.eat("order") {
+            if !self.eat("by") {
+                return Err(self.unexpected("`by`"));
+            }
+            filter.order_by = Some(self.sorting()?);
+        }
+        if self.eat("limit") {
+            filter.limit = Some(self.integer()?);
+        }
+        if self.eat("offset") {
+            filter.offset = Some(self.integer()?);
+        }
+        if self.peek().is_some() {
+            return Err(self.unexpected("`and`, `order by`, `limit` or `offset`"));
+        }
+        Ok(filter)
+    }
+
+    fn field(&mut self) -> Result<(&'static Field, Range<usize>), ParseError> {
+        match self.next("a field name")? {
+            (Token::Ident(name), span) => match find(self.fields, &name) {
+                Some(field) => Ok((field, span)),
+                None => Err(ParseError::new(ParseErrorKind::UnknownField(name), span)),
+            },
+            (_, span) => Err(unexpected("a field name", span)),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Filter, ParseError> {
+        let (field, _) = self.field()?;
+
+        let expected = "an operator";
+        let (op, span) = match self.next(expected)? {
+            (Token::Eq, span) => (Operator::Equals, span),
+            (Token::Ne, span) => (Operator::Not, span),
+            (Token::Like, span) => (Operator::Like, span),
+            (Token::NotLike, span) => (Operator::NotLike, span),
+            (Token::Ge, span) => (Operator::Min, span),
+            (Token::Le, span) => (Operator::Max, span),
+            (Token::Gt, span) => (Operator::GreaterThan, span),
+            (Token::Lt, span) => (Operator::SmallerThan, span),
+            (Token::Amp, span) => (Operator::BitwiseAnd, span),
+            (ref t, span) if t.is_keyword("in") => (Operator::In, span),
+            (ref t, span) if t.is_keyword("not") => match self.next("`in`")? {
+                (ref t, end) if t.is_keyword("in") => (Operator::NotIn, span.start..end.end),
+                (_, span) => return Err(unexpected("`in`", span)),
+            },
+            (_, span) => return Err(unexpected(expected, span)),
+        };
+        if !field.supports(&op) {
+            let kind = ParseErrorKind::UnsupportedOperator {
+                field: field.name().to_owned(),
+                op,
+            };
+            return Err(ParseError::new(kind, span));
+        }
+
+        let value = match op {
+            Operator::In | Operator::NotIn => {
+                self.expect(Token::LParen, "`(`")?;
+                let mut values = vec![self.value(field)?];
+                loop {
+                    match self.next("`,` or `)`")? {
+                        (Token::Comma, _) => values.push(self.value(field)?),
+                        (Token::RParen, _) => break,
+                        (_, span) => return Err(unexpected("`,` or `)`", span)),
+                    }
+                }
+                OneOrMany::Many(values)
+            }
+            _ => OneOrMany::One(self.value(field)?),
+        };
+        Ok(Filter::new(field.name(), op, value))
+    }
+
+    fn value(&mut self, field: &Field) -> Result<String, ParseError> {
+        match (self.next("a value")?, field.kind()) {
+            ((Token::Str(s), _), ValueKind::Text | ValueKind::Any) => Ok(s),
+            ((Token::Number(n), _), ValueKind::Number | ValueKind::Any) => Ok(n),
+            ((Token::Str(_), span), expected) | ((Token::Number(_), span), expected) => {
+                let kind = ParseErrorKind::InvalidValue {
+                    field: field.name().to_owned(),
+                    expected,
+                };
+                Err(ParseError::new(kind, span))
+            }
+            ((_, span), _) => Err(unexpected("a value", span)),
+        }
+    }
+
+    fn sorting(&mut self) -> Result<Sorting, ParseError> {
+        let desc = matches!(self.peek(), Some(Token::Minus));
+        if desc {
+            self.pos += 1;
+        }
+        let (field, span) = self.field()?;
+        if !field.sortable() {
+            let kind = ParseErrorKind::NotSortable(field.name().to_owned());
+            return Err(ParseError::new(kind, span));
+        }
+        let name = field.name().to_owned();
+        if desc {
+            Ok(Sorting::Desc(name))
+        } else {
+            Ok(Sorting::Asc(name))
+        }
+    }
+
+    fn integer(&mut self) -> Result<usize, ParseError> {
+        match self.next("a number")? {
+            (Token::Number(n), span) => n
+                .parse()
+                .map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber, span)),
+            (_, span) => Err(unexpected("a number", span)),
+        }
+    }
+}
+
+fn unexpected(expected: &'static str, span: Range<usize>) -> ParseError {
+    ParseError::new(ParseErrorKind::UnexpectedToken { expected }, span)
+}
+
+pub(crate) fn parse(input: &str, fields: &'static [Field]) -> Result<Filter, ParseError> {
+    let tokens = tokenize(input)?;
+    Parser {
+        tokens,
+        pos: 0,
+        len: input.len(),
+        fields,
+    }
+    .parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QueryString;
+
+    type Endpoint = Any;
+
+    filter!(Name, NAME, "name", String: Eq, NotEq, Like, In, OrderBy);
+    filter!(Downloads, DOWNLOADS, "downloads", u32: Cmp, In, OrderBy);
+    filter!(Visible, VISIBLE, "visible", crate::mods::Visibility: Eq);
+
+    const SCHEMA: Schema = Schema::new(&[Name::FIELD, Downloads::FIELD, Visible::FIELD]);
+
+    fn parse(input: &str) -> Result<String, ParseError> {
+        super::parse(input, SCHEMA.fields()).map(|f| f.to_query_string())
+    }
+
+    fn error(input: &str) -> (ParseErrorKind, &str) {
+        let err = parse(input).unwrap_err();
+        (err.kind().clone(), &input[err.span()])
+    }
+
+    #[test]
+    fn expressions() {
+        let query = parse(r#"name ~ "*zombie*" and downloads > 1000 order by -downloads limit 50"#);
+        assert_eq!(
+            query.unwrap(),
+            "downloads-gt=1000&name-lk=*zombie*&_sort=-downloads&_limit=50"
+        );
+
+        let query = parse(r#"name NOT IN ("a \"b\"", "c") AND visible = 1 OFFSET 10"#);
+        assert_eq!(
+            query.unwrap(),
+            "name-not-in=a+%22b%22%2Cc&visible=1&_offset=10"
+        );
+
+        assert_eq!(parse("downloads <= -1.5").unwrap(), "downloads-max=-1.5");
+        assert_eq!(
+            parse("order by name limit 5").unwrap(),
+            "_sort=name&_limit=5"
+        );
+        assert_eq!(parse("").unwrap(), "");
+    }
+
+    #[test]
+    fn errors() {
+        let unknown = ParseErrorKind::UnknownField("foo".to_owned());
+        assert_eq!(error(r#"name = "a" and foo = 1"#), (unknown, "foo"));
+
+        let kind = ParseErrorKind::UnsupportedOperator {
+            field: "visible".to_owned(),
+            op: Operator::Like,
+        };
+        assert_eq!(error(r#"visible ~ "a""#), (kind, "~"));
+
+        let kind = ParseErrorKind::InvalidValue {
+            field: "downloads".to_owned(),
+            expected: ValueKind::Number,
+        };
+        assert_eq!(error(r#"downloads in (1, "2")"#), (kind, r#""2""#));
+
+        let kind = ParseErrorKind::NotSortable("visible".to_owned());
+        assert_eq!(error("order by -visible"), (kind, "visible"));
+
+        let kind = ParseErrorKind::UnexpectedChar('$');
+        assert_eq!(error("name = $"), (kind, "$"));
+
+        assert_eq!(
+            error(r#"name = "abc"#),
+            (ParseErrorKind::UnterminatedString, r#""abc"#)
+        );
+        assert_eq!(error("limit -1"), (ParseErrorKind::InvalidNumber, "-1"));
+
+        let kind = ParseErrorKind::UnexpectedEnd {
+            expected: "a value",
+        };
+        assert_eq!(error("name ="), (kind, ""));
+
+        let kind = ParseErrorKind::UnexpectedToken {
+            expected: "`and`, `order by`, `limit` or `offset`",
+        };
+        assert_eq!(error(r#"name = "a" name = "b""#), (kind, "name"));
+    }
+}
@@ -0,0 +1,766 @@
+//! Client-side evaluation of filters
+//!
+//! A `Filter` can be tested against entities that are already in memory, e.g. a cached list of
+//! mods, with the [`Matches`] trait. The operators behave like the filters of the mod.io API:
+//!
+//! - Text is compared case-insensitively and `*` is the wildcard of `Like` and `NotLike`.
+//! - `BitwiseAnd` matches if any of the bits are set.
+//! - List fields like `tags` match if any of their elements matches. Negated operators match
+//!   if none of them does.
+//! - Missing optional values never match, like `NULL` values in SQL.
+//! - The `Fulltext` filter matches if every word of the query is found.
+//!
+//! Filters of fields the entity doesn't know never match, like the API rejecting the request,
+//! e.g. the shared `Name` filter on comments.
+//!
+//! ```
+//! use modio::filter::prelude::*;
+//! use modio::filter::endpoint;
+//! use modio::filter::matches::Matches;
+//! use modio::mods::filters::Tags;
+//! use modio::mods::Mod;
+//!
+//! fn maps(filter: &Filter<endpoint::Mods>, mods: Vec<Mod>) -> Vec<Mod> {
+//!     filter.apply(mods)
+//! }
+//!
+//! let filter = Tags::eq("Map").and(Name::like("*castle*")).order_by(Name::asc()).limit(10);
+//! # let _ = maps(&filter, vec![]);
+//! ```
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
+use super::endpoint::{self, Scope};
+use super::{Filter, FilterEntry, OneOrMany, Operator, Sorting};
+use crate::metadata::encode_kvp;
+use crate::types::game::Game;
+use crate::types::mods::{Comment, Event as ModEvent, File, Mod, Rating, Statistics, TeamMember};
+use crate::types::{Event, User};
+
+/// The value of an entity field compared by the filters.
+#[derive(Clone, Debug)]
+pub enum Value<'a> {
+    Number(f64),
+    Text(Cow<'a, str>),
+    /// Matches if any of the values matches.
+    List(Vec<Value<'a>>),
+    /// Missing optional value, never matches.
+    Null,
+}
+
+impl<'a> From<&'a str> for Value<'a> {
+    fn from(s: &'a str) -> Value<'a> {
+        Value::Text(Cow::Borrowed(s))
+    }
+}
+
+impl<'a> From<&'a String> for Value<'a> {
+    fn from(s: &'a String) -> Value<'a> {
+        Value::Text(Cow::Borrowed(s))
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(s: String) -> Self {
+        Value::Text(Cow::Owned(s))
+    }
+}
+
+impl<'a, T: Into<Value<'a>>> From<Option<T>> for Value<'a> {
+    fn from(value: Option<T>) -> Value<'a> {
+        value.map(Into::into).unwrap_or(Value::Null)
+    }
+}
+
+macro_rules! impl_number_value {
+    ($($type:ty),*) => {
+        $(
+            impl From<$type> for Value<'_> {
+                fn from(n: $type) -> Self {
+                    Value::Number(n.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_number_value!(u8, i8, u32, i32, f32);
+
+impl From<u64> for Value<'_> {
+    fn from(n: u64) -> Self {
+        Value::Number(n as f64)
+    }
+}
+
+/// Entities whose fields can be tested by the filters of an endpoint.
+pub trait Entity {
+    /// The endpoint marker of the filters for this entity.
+    type Endpoint;
+
+    /// Returns the value of the field `name`, or `None` if the entity doesn't have the field.
+    ///
+    /// The `_q` field is the text searched by the `Fulltext` filter.
+    fn field(&self, name: &str) -> Option<Value<'_>>;
+}
+
+/// Test entities against a `Filter` without a request to mod.io.
+pub trait Matches<T> {
+    /// Returns `true` if the entity passes every filter. Filters of unknown fields never match.
+    fn matches(&self, entity: &T) -> bool;
+
+    /// Sort the entities by the sorting of the filter. The order of entities with equal sort
+    /// values is kept.
+    fn sort(&self, entities: &mut [T]);
+
+    /// Filter, sort and paginate the entities like the list endpoint would.
+    fn apply(&self, entities: Vec<T>) -> Vec<T>;
+}
+
+impl<E, T> Matches<T> for Filter<E>
+where
+    T: Entity,
+    E: Scope<T::Endpoint>,
+{
+    fn matches(&self, entity: &T) -> bool {
+        self.filters.iter().all(|f| match entity.field(&f.name) {
+            Some(value) => entry_matches(f, &value),
+            None => false,
+        })
+    }
+
+    fn sort(&self, entities: &mut [T]) {
+        let (name, desc) = match self.order_by {
+            Some(Sorting::Asc(ref name)) => (name, false),
+            Some(Sorting::Desc(ref name)) => (name, true),
+            None => return,
+        };
+        entities.sort_by(|a, b| {
+            let ord = match (a.field(name), b.field(name)) {
+                (Some(a), Some(b)) => compare(&a, &b),
+                _ => Ordering::Equal,
+            };
+            if desc {
+                ord.reverse()
+            } else {
+                ord
+            }
+        });
+    }
+
+    fn apply(&self, mut entities: Vec<T>) -> Vec<T> {
+        entities.retain(|e| self.matches(e));
+        self.sort(&mut entities);
+        entities
+            .into_iter()
+            .skip(self.offset.unwrap_or(0))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
+fn entry_matches(entry: &FilterEntry, value: &Value<'_>) -> bool {
+    let args = match entry.value {
+        OneOrMany::One(ref v) => std::slice::from_ref(v),
+        OneOrMany::Many(ref v) => &v[..],
+    };
+    if entry.name == "_q" {
+        return args.iter().all(|q| fulltext(value, q));
+    }
+    let (op, negate) = match entry.op {
+        Operator::Not => (&Operator::Equals, true),
+        Operator::NotLike => (&Operator::Like, true),
+        Operator::In => (&Operator::Equals, false),
+        Operator::NotIn => (&Operator::Equals, true),
+        ref op => (op, false),
+    };
+    if let Value::Null = value {
+        return false;
+    }
+    let hit = match entry.op {
+        Operator::In | Operator::NotIn => args.iter().any(|arg| test(value, op, arg)),
+        _ => args.iter().all(|arg| test(value, op, arg)),
+    };
+    hit != negate
+}
+
+/// Test a value with one of the positive operators.
+fn test(value: &Value<'_>, op: &Operator, arg: &str) -> bool {
+    match value {
+        Value::Null => false,
+        Value::List(values) => values.iter().any(|v| test(v, op, arg)),
+        Value::Number(n) => match op {
+            Operator::Like => like(&number_to_string(*n), arg),
+            Operator::BitwiseAnd => match arg.parse::<u64>() {
+                Ok(bits) => (*n as u64) & bits != 0,
+                Err(_) => false,
+            },
+            _ => match arg.parse::<f64>() {
+                Ok(arg) => ordering_matches(op, n.partial_cmp(&arg)),
+                Err(_) => false,
+            },
+        },
+        Value::Text(s) => match op {
+            Operator::Like => like(s, arg),
+            Operator::BitwiseAnd => match (s.parse::<u64>(), arg.parse::<u64>()) {
+                (Ok(n), Ok(bits)) => n & bits != 0,
+                _ => false,
+            },
+            _ => ordering_matches(op, Some(compare_text(s, arg))),
+        },
+    }
+}
+
+fn ordering_matches(op: &Operator, ord: Option<Ordering>) -> bool {
+    let ord = match ord {
+        Some(ord) => ord,
+        None => return false,
+    };
+    match op {
+        Operator::Equals => ord == Ordering::Equal,
+        Operator::Min => ord != Ordering::Less,
+        Operator::Max => ord != Ordering::Greater,
+        Operator::SmallerThan => ord == Ordering::Less,
+        Operator::GreaterThan => ord == Ordering::Greater,
+        _ => false,
+    }
+}
+
+/// Compare two values for sorting. Missing values are sorted first like `NULL` in SQL.
+pub(crate) fn compare(a: &Value<'_>, b: &Value<'_>) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (Value::Text(a), Value::Text(b)) => compare_text(a, b),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Less,
+        (_, Value::Null) => Ordering::Greater,
+        _ => Ordering::Equal,
+    }
+}
+
+/// Numbers stored as text are compared numerically, everything else case-insensitively.
+fn compare_text(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+fn number_to_string(n: f64) -> String {
+    if n.fract() == 0.0 {
+        format!("{}", n as i64)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Case-insensitive `LIKE` where `*` matches any sequence of characters.
+fn like(value: &str, pattern: &str) -> bool {
+    let value = value.to_lowercase().chars().collect::<Vec<_>>();
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut v, mut p) = (0, 0);
+    let mut backtrack = None;
+    while v < value.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == value[v] {
+            p += 1;
+            v += 1;
+        } else if let Some((bp, bv)) = backtrack {
+            // Let the last wildcard consume one more character.
+            p = bp + 1;
+            v = bv + 1;
+            backtrack = Some((bp, bv + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn fulltext(value: &Value<'_>, query: &str) -> bool {
+    match value {
+        Value::Text(s) => {
+            let s = s.to_lowercase();
+            query
+                .split_whitespace()
+                .all(|word| s.contains(&word.to_lowercase()))
+        }
+        Value::List(values) => values.iter().any(|v| fulltext(v, query)),
+        Value::Number(_) | Value::Null => false,
+    }
+}
+
+// impl Entity for the response types {{{
+impl Entity for Game {
+    type Endpoint = endpoint::Games;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "_q" | "name" => (&self.name).into(),
+            "id" => self.id.into(),
+            "status" => (self.status as u8).into(),
+            "submitted_by" => self.submitted_by.id.into(),
+            "date_added" => self.date_added.into(),
+            "date_updated" => self.date_updated.into(),
+            "date_live" => self.date_live.into(),
+            "name_id" => (&self.name_id).into(),
+            "summary" => (&self.summary).into(),
+            "instructions_url" => self.instructions_url.as_ref().map(|u| u.as_str()).into(),
+            "ugc_name" => (&self.ugc_name).into(),
+            "presentation_option" => (self.presentation_option as u8).into(),
+            "submission_option" => (self.submission_option as u8).into(),
+            "curation_option" => (self.curation_option as u8).into(),
+            "community_options" => self.community_options.bits().into(),
+            "revenue_options" => self.revenue_options.bits().into(),
+            "api_access_options" => self.api_access_options.bits().into(),
+            "maturity_options" => (self.maturity_options as u8).into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for Mod {
+    type Endpoint = endpoint::Mods;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "_q" | "name" => (&self.name).into(),
+            "id" => self.id.into(),
+            "game_id" => self.game_id.into(),
+            "status" => (self.status as u8).into(),
+            "visible" => (self.visible as u8).into(),
+            "submitted_by" => self.submitted_by.id.into(),
+            "date_added" => self.date_added.into(),
+            "date_updated" => self.date_updated.into(),
+            "date_live" => self.date_live.into(),
+            "maturity_option" => self.maturity_option.bits().into(),
+            "name_id" => (&self.name_id).into(),
+            "summary" => (&self.summary).into(),
+            "description" => self.description.as_ref().into(),
+            "homepage_url" => self.homepage_url.as_ref().map(|u| u.as_str()).into(),
+            "modfile" => self.modfile.as_ref().map(|f| f.id).into(),
+            "metadata_blob" => self.metadata_blob.as_ref().into(),
+            "metadata_kvp" => Value::List(
+                self.metadata
+                    .iter()
+                    .flat_map(|(k, values)| values.iter().map(move |v| encode_kvp(k, v)))
+                    .map(Into::into)
+                    .collect(),
+            ),
+            "tags" => Value::List(self.tags.iter().map(|t| (&t.name).into()).collect()),
+            // mod.io sorts these fields in descending order by default, `popular` starts with the
+            // first rank position.
+            "downloads" => Value::Number(-f64::from(self.stats.downloads_total)),
+            "popular" => self.stats.popularity.rank_position.into(),
+            "ratings" => Value::Number(-f64::from(self.stats.ratings.weighted_aggregate)),
+            "subscribers" => Value::Number(-f64::from(self.stats.subscribers_total)),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for ModEvent {
+    type Endpoint = endpoint::ModEvents;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "id" => self.id.into(),
+            "mod_id" => self.mod_id.into(),
+            "user_id" => self.user_id.into(),
+            "date_added" => self.date_added.into(),
+            "event_type" => self.event_type.to_string().into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for Statistics {
+    type Endpoint = endpoint::ModStats;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "mod_id" => self.mod_id.into(),
+            "popularity_rank_position" => self.popularity.rank_position.into(),
+            "downloads_total" => self.downloads_total.into(),
+            "subscribers_total" => self.subscribers_total.into(),
+            "ratings_positive" => self.ratings.positive.into(),
+            "ratings_negative" => self.ratings.negative.into(),
+            "date_expires" => self.date_expires.into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for File {
+    type Endpoint = endpoint::Files;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "_q" | "filename" => (&self.filename).into(),
+            "id" => self.id.into(),
+            "mod_id" => self.mod_id.into(),
+            "date_added" => self.date_added.into(),
+            "date_scanned" => self.date_scanned.into(),
+            "virus_status" => (self.virus_status as u8).into(),
+            "virus_positive" => (self.virus_positive as u8).into(),
+            "filesize" => self.filesize.into(),
+            "filehash" => (&self.filehash.md5).into(),
+            "version" => self.version.as_ref().into(),
+            "changelog" => self.changelog.as_ref().into(),
+            "metadata_blob" => self.metadata_blob.as_ref().into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for Comment {
+    type Endpoint = endpoint::Comments;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "_q" | "content" => (&self.content).into(),
+            "id" => self.id.into(),
+            "mod_id" => self.mod_id.into(),
+            "submitted_by" => self.user.id.into(),
+            "date_added" => self.date_added.into(),
+            "reply_id" => self.reply_id.into(),
+            "thread_position" => (&self.thread_position).into(),
+            "karma" => self.karma.into(),
+            "karma_guest" => self.karma_guest.into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for User {
+    type Endpoint = endpoint::Users;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "_q" | "username" => (&self.username).into(),
+            "id" => self.id.into(),
+            "name_id" => (&self.name_id).into(),
+            "date_online" => self.date_online.into(),
+            "timezone" => (&self.timezone).into(),
+            "language" => (&self.language).into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for TeamMember {
+    type Endpoint = endpoint::Teams;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "_q" | "username" => (&self.user.username).into(),
+            "id" => self.id.into(),
+            "user_id" => self.user.id.into(),
+            "level" => (self.level as u8).into(),
+            "date_added" => self.date_added.into(),
+            "position" => (&self.position).into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for Event {
+    type Endpoint = endpoint::UserEvents;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        Some(match name {
+            "id" => self.id.into(),
+            "game_id" => self.game_id.into(),
+            "mod_id" => self.mod_id.into(),
+            "user_id" => self.user_id.into(),
+            "date_added" => self.date_added.into(),
+            "event_type" => self.event_type.to_string().into(),
+            _ => return None,
+        })
+    }
+}
+
+impl Entity for Rating {
+    type Endpoint = endpoint::Ratings;
+
+    fn field(&self, name: &str) -> Option<Value<'_>> {
+        let (game_id, mod_id, date_added, rating) = match *self {
+            Rating::Positive {
+                game_id,
+                mod_id,
+                date_added,
+            } => (game_id, mod_id, date_added, 1),
+            Rating::Negative {
+                game_id,
+                mod_id,
+                date_added,
+            } => (game_id, mod_id, date_added, -1),
+        };
+        Some(match name {
+            "game_id" => game_id.into(),
+            "mod_id" => mod_id.into(),
+            "date_added" => date_added.into(),
+            "rating" => Value::Number(rating.into()),
+            _ => return None,
+        })
+    }
+}
+// }}}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tests::{mod_, user};
+
+    struct Item {
+        id: u32,
+        name: &'static str,
+        tags: Vec<&'static str>,
+        flags: u8,
+        homepage: Option<&'static str>,
+    }
+
+    impl Entity for Item {
+        type Endpoint = endpoint::Any;
+
+        fn field(&self, name: &str) -> Option<Value<'_>> {
+            Some(match name {
+                "_q" | "name" => self.name.into(),
+                "id" => self.id.into(),
+                "tags" => Value::List(self.tags.iter().map(|&t| t.into()).collect()),
+                "flags" => self.flags.into(),
+                "homepage_url" => self.homepage.into(),
+                _ => return None,
+            })
+        }
+    }
+
+    fn items() -> Vec<Item> {
+        vec![
+            Item {
+                id: 1,
+                name: "Castle Map",
+                tags: vec!["Map", "Medieval"],
+                flags: 0b01,
+                homepage: Some("https://example.com"),
+            },
+            Item {
+                id: 2,
+                name: "castle walls",
+                tags: vec!["Texture"],
+                flags: 0b10,
+                homepage: None,
+            },
+            Item {
+                id: 3,
+                name: "Space Map",
+                tags: vec!["Map"],
+                flags: 0b11,
+                homepage: None,
+            },
+        ]
+    }
+
+    fn ids(filter: &Filter, items: Vec<Item>) -> Vec<u32> {
+        filter.apply(items).into_iter().map(|i| i.id).collect()
+    }
+
+    #[test]
+    fn operators() {
+        use crate::filter::custom_filter;
+        use crate::filter::prelude::*;
+
+        let tags = custom_filter("tags", Operator::Equals, "map");
+        let homepage = custom_filter("homepage_url", Operator::Not, "https://foo.com");
+        let flags = custom_filter("flags", Operator::BitwiseAnd, 2);
+
+        assert_eq!(ids(&Id::eq(2), items()), [2]);
+        assert_eq!(ids(&Id::ne(2), items()), [1, 3]);
+        assert_eq!(ids(&Id::_in(vec![1, 3]), items()), [1, 3]);
+        assert_eq!(ids(&Id::not_in(vec![1, 3]), items()), [2]);
+        assert_eq!(ids(&Id::ge(2), items()), [2, 3]);
+        assert_eq!(ids(&Id::le(2), items()), [1, 2]);
+        assert_eq!(ids(&Id::lt(2), items()), [1]);
+        assert_eq!(ids(&Id::gt(2), items()), [3]);
+        assert_eq!(ids(&Name::eq("CASTLE MAP"), items()), [1]);
+        assert_eq!(ids(&Name::like("castle*"), items()), [1, 2]);
+        assert_eq!(ids(&Name::like("*a*a*"), items()), [1, 2, 3]);
+        assert_eq!(ids(&Name::not_like("*map"), items()), [2]);
+        assert_eq!(ids(&Fulltext::eq("map castle"), items()), [1]);
+        assert_eq!(ids(&tags, items()), [1, 3]);
+        assert_eq!(ids(&homepage, items()), [1]);
+        assert_eq!(ids(&flags, items()), [2, 3]);
+        assert!(ids(&custom_filter("unknown", Operator::Equals, 1), items()).is_empty());
+    }
+
+    #[test]
+    fn sorting_and_pagination() {
+        use crate::filter::prelude::*;
+
+        assert_eq!(ids(&Name::asc(), items()), [1, 2, 3]);
+        assert_eq!(ids(&Name::desc(), items()), [3, 2, 1]);
+        assert_eq!(ids(&Id::desc().offset(1).limit(1), items()), [2]);
+    }
+
+    fn mod_list() -> Vec<Mod> {
+        vec![
+            mod_(
+                1,
+                "Castle Map",
+                (10, 0.5, 30),
+                &["Map"],
+                &[("difficulty", "hard")],
+            ),
+            mod_(
+                2,
+                "Castle Walls",
+                (30, 0.9, 10),
+                &["Texture"],
+                &[("difficulty", "easy")],
+            ),
+            mod_(3, "Space Map", (20, 0.1, 20), &["Map", "Sci-Fi"], &[]),
+        ]
+    }
+
+    fn mod_ids<E: Scope<endpoint::Mods>>(filter: &Filter<E>) -> Vec<u32> {
+        filter.apply(mod_list()).into_iter().map(|m| m.id).collect()
+    }
+
+    #[test]
+    fn mods() {
+        use crate::filter::prelude::*;
+        use crate::mods::filters::{Downloads, MetadataKVP, Popular, Ratings, Subscribers, Tags};
+
+        assert_eq!(mod_ids(&Name::like("castle*")), [1, 2]);
+        assert_eq!(mod_ids(&Fulltext::eq("map")), [1, 3]);
+        assert_eq!(mod_ids(&Tags::eq("map")), [1, 3]);
+        assert_eq!(mod_ids(&Tags::ne("map")), [2]);
+        let key = MetadataKVP::key("difficulty");
+        assert_eq!(mod_ids(&key.eq("hard").unwrap()), [1]);
+        assert_eq!(mod_ids(&key.like("*").unwrap()), [1, 2]);
+        assert_eq!(mod_ids(&key.not_in(vec!["hard"]).unwrap()), [2, 3]);
+
+        // mod.io returns the mods with the most downloads, ratings and subscribers first.
+        assert_eq!(mod_ids(&Downloads::asc()), [2, 3, 1]);
+        assert_eq!(mod_ids(&Downloads::desc()), [1, 3, 2]);
+        assert_eq!(mod_ids(&Ratings::asc()), [2, 1, 3]);
+        assert_eq!(mod_ids(&Subscribers::asc()), [1, 3, 2]);
+        assert_eq!(mod_ids(&Popular::asc()), [1, 2, 3]);
+    }
+
+    #[test]
+    fn files() {
+        use crate::files::filters::{Filename, Filesize, Version};
+        use crate::filter::prelude::*;
+
+        let file = |id: u32, version: &str, filesize: u64| -> File {
+            serde_json::from_value(json!({
+                "id": id, "mod_id": 2, "date_added": id, "date_scanned": id, "virus_status": 1,
+                "virus_positive": 0, "virustotal_hash": null, "filesize": filesize,
+                "filehash": {"md5": "5eb63bbbe01eeed093cb22bb8f5acdc3"},
+                "filename": format!("mod-{}.zip", version), "version": version,
+                "changelog": null, "metadata_blob": null,
+                "download": {"binary_url": "https://example.com/mod.zip", "date_expires": 0},
+            }))
+            .unwrap()
+        };
+        let files = || {
+            vec![
+                file(1, "1.0", 100),
+                file(2, "1.1", 200),
+                file(3, "2.0", 300),
+            ]
+        };
+        let ids = |files: Vec<File>| files.into_iter().map(|f| f.id).collect::<Vec<_>>();
+
+        assert_eq!(ids(Version::like("1.*").apply(files())), [1, 2]);
+        assert_eq!(ids(Filename::eq("MOD-2.0.ZIP").apply(files())), [3]);
+        let filter = Filesize::gt(100).order_by(Filesize::desc());
+        assert_eq!(ids(filter.apply(files())), [3, 2]);
+        assert_eq!(ids(Fulltext::eq("mod-1.1").apply(files())), [2]);
+        assert!(Name::eq("mod-1.0.zip").apply(files()).is_empty());
+    }
+
+    #[test]
+    fn comments_and_users() {
+        use crate::comments::filters::{Content, Karma};
+        use crate::filter::prelude::*;
+        use crate::users::filters::Username;
+
+        let comment = |id: u32, content: &str, karma: u32| -> Comment {
+            serde_json::from_value(json!({
+                "id": id, "mod_id": 2, "user": user(id, "Foo"), "date_added": id,
+                "reply_id": 0, "thread_position": "01", "karma": karma, "karma_guest": 0,
+                "content": content,
+            }))
+            .unwrap()
+        };
+        let comments = vec![
+            comment(1, "Great map", 5),
+            comment(2, "Crashes on start", 0),
+        ];
+        let filter = Content::like("*map*").and(Karma::ge(1));
+        assert_eq!(filter.apply(comments).len(), 1);
+
+        // Comments have no name, the shared filter matches nothing.
+        let comments = vec![comment(1, "Great map", 5)];
+        assert!(Name::eq("Great map").apply(comments).is_empty());
+
+        let users: Vec<User> = vec![
+            serde_json::from_value(user(1, "Foo")).unwrap(),
+            serde_json::from_value(user(2, "Bar")).unwrap(),
+        ];
+        let users = Username::like("b*").apply(users);
+        assert_eq!(users.iter().map(|u| u.id).collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn events_and_ratings() {
+        use crate::filter::Eq;
+        use crate::me::filters::{events, ratings};
+        use crate::types::EventType;
+
+        let event = |id: u32, event_type: &str| -> Event {
+            serde_json::from_value(json!({
+                "id": id, "game_id": 1, "mod_id": 2, "user_id": 3, "date_added": id,
+                "event_type": event_type,
+            }))
+            .unwrap()
+        };
+        let events = vec![event(1, "USER_SUBSCRIBE"), event(2, "USER_UNSUBSCRIBE")];
+        let filter = events::EventType::eq(EventType::UserUnsubscribe);
+        assert_eq!(
+            filter
+                .apply(events)
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>(),
+            [2]
+        );
+
+        let rating = |mod_id: u32, rating: i8| -> Rating {
+            serde_json::from_value(json!({
+                "game_id": 1, "mod_id": mod_id, "rating": rating, "date_added": mod_id,
+            }))
+            .unwrap()
+        };
+        let filter = ratings::Rating::positive();
+        let ratings = filter.apply(vec![rating(1, 1), rating(2, -1), rating(3, 1)]);
+        assert_eq!(ratings.len(), 2);
+        assert!(!ratings::Rating::eq(0).matches(&rating(1, -1)));
+    }
+
+    #[test]
+    fn like() {
+        assert!(super::like("Castle", "castle"));
+        assert!(super::like("Castle", "*"));
+        assert!(super::like("Castle", "c*e"));
+        assert!(super::like("abcbc", "*bc"));
+        assert!(!super::like("Castle", "c*x"));
+        assert!(!super::like("Castle", "cast"));
+    }
+}
@@ -5,8 +5,10 @@ use std::path::Path;
 use mime::IMAGE_STAR;
 use url::form_urlencoded;
 
-use crate::multipart::{FileSource, FileStream};
+use crate::error::Result;
+use crate::multipart::FileSource;
 use crate::prelude::*;
+use crate::validation::{self, Validator};
 use crate::ModRef;
 use crate::Mods;
 
@@ -30,7 +32,7 @@ impl MyGames {
     /// List all games the authenticated user added or is team member of. [required: token]
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<Game>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Games>>) -> Future<List<Game>> {
         token_required!(self.modio);
         let mut uri = vec!["/me/games".to_owned()];
         let query = filter.to_query_string();
@@ -44,7 +46,7 @@ impl MyGames {
     /// [required: token]
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<Game> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Games>>) -> Stream<Game> {
         token_required!(s self.modio);
         let mut uri = vec!["/me/games".to_owned()];
         let query = filter.to_query_string();
@@ -72,7 +74,7 @@ impl Games {
     /// List all games.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<Game>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Games>>) -> Future<List<Game>> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -84,7 +86,7 @@ impl Games {
     /// Provides a stream over all games.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<Game> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Games>>) -> Stream<Game> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -93,6 +95,13 @@ impl Games {
         self.modio.stream(&uri.join("?"))
     }
 
+    /// Provides a stream over all games that pass any of the alternatives.
+    ///
+    /// See [OR-queries](../filter/any/index.html).
+    pub fn iter_any(&self, filter: &AnyOf<impl Scope<endpoint::Games>>) -> Stream<Game> {
+        self.modio.stream_any(&self.path(""), filter)
+    }
+
     /// Return a reference to a game.
     pub fn get(&self, id: u32) -> GameRef {
         GameRef::new(self.modio.clone(), id)
@@ -144,6 +153,9 @@ impl GameRef {
     /// Add or edit new media to a game. [required: token]
     pub fn add_media(&self, media: GameMediaOptions) -> Future<()> {
         token_required!(self.modio);
+        if let Err(e) = media.validate() {
+            return future_err!(e);
+        }
         Box::new(
             self.modio
                 .post_form::<ModioMessage, _>(&self.path("/media"), media)
@@ -215,16 +227,41 @@ pub mod filters {
     #[doc(inline)]
     pub use crate::filter::prelude::SubmittedBy;
 
-    filter!(Summary, SUMMARY, "summary", Eq, NotEq, Like);
-    filter!(InstructionsUrl, INSTRUCTIONS_URL, "instructions_url", Eq, NotEq, In, Like);
-    filter!(UgcName, UGC_NAME, "ugc_name", Eq, NotEq, In, Like);
-    filter!(PresentationOption, PRESENTATION_OPTION, "presentation_option", Eq, NotEq, In, Cmp, Bit);
-    filter!(SubmissionOption, SUBMISSION_OPTION, "submission_option", Eq, NotEq, In, Cmp, Bit);
-    filter!(CurationOption, CURATION_OPTION, "curation_option", Eq, NotEq, In, Cmp, Bit);
-    filter!(CommunityOptions, COMMUNITY_OPTIONS, "community_options", Eq, NotEq, In, Cmp, Bit);
-    filter!(RevenueOptions, REVENUE_OPTIONS, "revenue_options", Eq, NotEq, In, Cmp, Bit);
-    filter!(ApiAccessOptions, API_ACCESS_OPTIONS, "api_access_options", Eq, NotEq, In, Cmp, Bit);
-    filter!(MaturityOptions, MATURITY_OPTIONS, "maturity_options", Eq, NotEq, In, Cmp, Bit);
+    type Endpoint = crate::filter::endpoint::Games;
+
+    filter!(Summary, SUMMARY, "summary", String: Eq, NotEq, Like);
+    filter!(InstructionsUrl, INSTRUCTIONS_URL, "instructions_url", String: Eq, NotEq, In, Like);
+    filter!(UgcName, UGC_NAME, "ugc_name", String: Eq, NotEq, In, Like);
+    filter!(PresentationOption, PRESENTATION_OPTION, "presentation_option", crate::types::game::PresentationOption: Eq, NotEq, In, Cmp, Bit);
+    filter!(SubmissionOption, SUBMISSION_OPTION, "submission_option", crate::types::game::SubmissionOption: Eq, NotEq, In, Cmp, Bit);
+    filter!(CurationOption, CURATION_OPTION, "curation_option", crate::types::game::CurationOption: Eq, NotEq, In, Cmp, Bit);
+    filter!(CommunityOptions, COMMUNITY_OPTIONS, "community_options", crate::types::game::CommunityOptions: Eq, NotEq, In, Cmp, Bit);
+    filter!(RevenueOptions, REVENUE_OPTIONS, "revenue_options", crate::types::game::RevenueOptions: Eq, NotEq, In, Cmp, Bit);
+    filter!(ApiAccessOptions, API_ACCESS_OPTIONS, "api_access_options", crate::types::game::ApiAccessOptions: Eq, NotEq, In, Cmp, Bit);
+    filter!(MaturityOptions, MATURITY_OPTIONS, "maturity_options", crate::types::game::MaturityOptions: Eq, NotEq, In, Cmp, Bit);
+
+    /// The game fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+    pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+        Fulltext::FIELD,
+        Id::FIELD,
+        Name::FIELD,
+        NameId::FIELD,
+        Status::FIELD,
+        DateAdded::FIELD,
+        DateUpdated::FIELD,
+        DateLive::FIELD,
+        SubmittedBy::FIELD,
+        Summary::FIELD,
+        InstructionsUrl::FIELD,
+        UgcName::FIELD,
+        PresentationOption::FIELD,
+        SubmissionOption::FIELD,
+        CurationOption::FIELD,
+        CommunityOptions::FIELD,
+        RevenueOptions::FIELD,
+        ApiAccessOptions::FIELD,
+        MaturityOptions::FIELD,
+    ]);
 }
 
 #[derive(Default)]
@@ -352,11 +389,7 @@ impl GameMediaOptions {
             .map_or_else(String::new, ToString::to_string);
 
         Self {
-            logo: Some(FileSource {
-                inner: FileStream::open(logo),
-                filename,
-                mime: IMAGE_STAR,
-            }),
+            logo: Some(FileSource::open(logo, filename, IMAGE_STAR)),
             ..self
         }
     }
@@ -369,11 +402,7 @@ impl GameMediaOptions {
             .map_or_else(String::new, ToString::to_string);
 
         Self {
-            icon: Some(FileSource {
-                inner: FileStream::open(icon),
-                filename,
-                mime: IMAGE_STAR,
-            }),
+            icon: Some(FileSource::open(icon, filename, IMAGE_STAR)),
             ..self
         }
     }
@@ -386,14 +415,25 @@ impl GameMediaOptions {
             .map_or_else(String::new, ToString::to_string);
 
         Self {
-            header: Some(FileSource {
-                inner: FileStream::open(header),
-                filename,
-                mime: IMAGE_STAR,
-            }),
+            header: Some(FileSource::open(header, filename, IMAGE_STAR)),
             ..self
         }
     }
+
+    /// Check the format, size and dimensions of the images before uploading them.
+    pub fn validate(&self) -> Result<()> {
+        let mut v = Validator::new();
+        if let Some(ref logo) = self.logo {
+            v.image("logo", logo, &validation::GAME_LOGO);
+        }
+        if let Some(ref icon) = self.icon {
+            v.image("icon", icon, &validation::GAME_ICON);
+        }
+        if let Some(ref header) = self.header {
+            v.image("header", header, &validation::GAME_HEADER);
+        }
+        v.finish()
+    }
 }
 
 #[doc(hidden)]
@@ -0,0 +1,414 @@
+//! Extract downloaded mod archives
+//!
+//! Requires the `install` feature.
+//!
+//! The entries of an archive are checked before anything is written to disk. Archives with
+//! absolute paths, paths containing `..` or symbolic links are rejected and the size limits of
+//! [`InstallOptions`](struct.InstallOptions.html) protect against zip bombs.
+//!
+//! Existing files are never overwritten and symbolic links inside the target directory are
+//! never followed. If the extraction fails, only the files and directories created by it are
+//! removed.
+//!
+//! # Example
+//! ```no_run
+//! use modio::install::InstallOptions;
+//! use modio::{Credentials, DownloadAction, Error, Modio};
+//! use tokio::runtime::Runtime;
+//!
+//! fn main() -> Result<(), Error> {
+//!     let mut rt = Runtime::new().expect("new rt");
+//!     let modio = Modio::new(
+//!         Credentials::ApiKey(String::from("user-or-game-api-key")),
+//!     )?;
+//!
+//!     let action = DownloadAction::Primary {
+//!         game_id: 5,
+//!         mod_id: 19,
+//!     };
+//!     let options = InstallOptions::default().max_size(1024 * 1024 * 1024);
+//!     let manifest = rt.block_on(modio.install(action, "mods/19", options))?;
+//!     for path in &manifest.files {
+//!         println!("installed: {}", path.display());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::fs;
+use std::io::{self, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+use zip::read::ZipArchive;
+use zip::result::ZipError;
+
+use crate::error::{self, Error, Result};
+
+const S_IFMT: u32 = 0o170_000;
+const S_IFLNK: u32 = 0o120_000;
+
+/// Limits used while extracting an archive.
+#[derive(Clone, Debug)]
+pub struct InstallOptions {
+    max_size: u64,
+    max_file_size: u64,
+    max_entries: usize,
+}
+
+impl Default for InstallOptions {
+    /// Defaults to `4 GiB` in total, `2 GiB` per file and `65536` entries.
+    fn default() -> InstallOptions {
+        InstallOptions {
+            max_size: 4 * 1024 * 1024 * 1024,
+            max_file_size: 2 * 1024 * 1024 * 1024,
+            max_entries: 65536,
+        }
+    }
+}
+
+impl InstallOptions {
+    /// Set the maximum number of uncompressed bytes of all files.
+    pub fn max_size(self, max_size: u64) -> Self {
+        Self { max_size, ..self }
+    }
+
+    /// Set the maximum number of uncompressed bytes of a single file.
+    pub fn max_file_size(self, max_file_size: u64) -> Self {
+        Self {
+            max_file_size,
+            ..self
+        }
+    }
+
+    /// Set the maximum number of entries of an archive.
+    pub fn max_entries(self, max_entries: usize) -> Self {
+        Self {
+            max_entries,
+            ..self
+        }
+    }
+}
+
+/// The paths created by extracting an archive.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    /// The target directory.
+    pub root: PathBuf,
+    /// The extracted directories.
+    pub dirs: Vec<PathBuf>,
+    /// The extracted files.
+    pub files: Vec<PathBuf>,
+    /// The number of uncompressed bytes written.
+    pub size: u64,
+}
+
+/// Extract a zip archive into the directory `dir`.
+///
+/// All entries are validated before the first file is written. The uncompressed sizes are
+/// enforced while extracting because the sizes stored in the archive can't be trusted.
+///
+/// `dir` may already exist. An entry whose file already exists in `dir` fails the extraction
+/// with [`InstallError::Exists`](crate::error::InstallError::Exists), remove a previous install
+/// first.
+pub fn extract<R, P>(reader: R, dir: P, options: &InstallOptions) -> Result<Manifest>
+where
+    R: Read + Seek,
+    P: AsRef<Path>,
+{
+    let root = dir.as_ref().to_path_buf();
+    let mut archive = ZipArchive::new(reader).map_err(archive_error)?;
+
+    if archive.len() > options.max_entries {
+        return Err(error::install_too_many_entries(options.max_entries));
+    }
+
+    let mut entries = Vec::with_capacity(archive.len());
+    let mut declared = 0u64;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i).map_err(archive_error)?;
+        let path = entry_path(file.name())?;
+        if file.unix_mode().is_some_and(|m| m & S_IFMT == S_IFLNK) {
+            return Err(error::install_symlink(file.name()));
+        }
+        if file.size() > options.max_file_size {
+            return Err(error::install_too_large(file.name(), options.max_file_size));
+        }
+        declared = declared.saturating_add(file.size());
+        if declared > options.max_size {
+            return Err(error::install_too_large(file.name(), options.max_size));
+        }
+        entries.push((path, file.is_dir()));
+    }
+
+    let mut manifest = Manifest {
+        root: root.clone(),
+        ..Default::default()
+    };
+    let mut created = Vec::new();
+    match write_entries(&mut archive, entries, &mut manifest, &mut created, options) {
+        Ok(()) => Ok(manifest),
+        Err(e) => {
+            // Remove the files and directories created so far. Existing paths are never part
+            // of the manifest or `created`.
+            for file in &manifest.files {
+                let _ = fs::remove_file(file);
+            }
+            for dir in created.iter().rev() {
+                let _ = fs::remove_dir(dir);
+            }
+            Err(e)
+        }
+    }
+}
+
+fn write_entries<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    entries: Vec<(PathBuf, bool)>,
+    manifest: &mut Manifest,
+    created: &mut Vec<PathBuf>,
+    options: &InstallOptions,
+) -> Result<()> {
+    let root = manifest.root.clone();
+    create_root(&root, created)?;
+
+    for (i, (path, is_dir)) in entries.into_iter().enumerate() {
+        let file = archive.by_index(i).map_err(archive_error)?;
+        let name = file.name().to_string();
+        let target = root.join(&path);
+        if is_dir {
+            create_dirs(&root, &path, &name, created)?;
+            manifest.dirs.push(target);
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            create_dirs(&root, parent, &name, created)?;
+        }
+
+        let limit = std::cmp::min(options.max_file_size, options.max_size - manifest.size);
+
+        // `create_new` fails for existing files and doesn't follow a symbolic link in place of
+        // the file.
+        let mut out = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&target)
+        {
+            Ok(out) => out,
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(error::install_exists(name));
+            }
+            Err(e) => return Err(error::from(e)),
+        };
+        manifest.files.push(target);
+        let written = io::copy(&mut file.take(limit + 1), &mut out).map_err(error::from)?;
+        if written > limit {
+            return Err(error::install_too_large(name, limit));
+        }
+        manifest.size += written;
+    }
+    Ok(())
+}
+
+/// Create the target directory and its missing parents. The created directories are appended
+/// to `created`.
+fn create_root(dir: &Path, created: &mut Vec<PathBuf>) -> Result<()> {
+    let missing = dir
+        .ancestors()
+        .take_while(|p| !p.as_os_str().is_empty() && !p.exists())
+        .map(Path::to_path_buf)
+        .collect::<Vec<_>>();
+    fs::create_dir_all(dir).map_err(error::from)?;
+    created.extend(missing.into_iter().rev());
+    Ok(())
+}
+
+/// Create the directory `path` of the entry `name` below `root`. Existing symbolic links are
+/// rejected instead of followed. The created directories are appended to `created`.
+fn create_dirs(root: &Path, path: &Path, name: &str, created: &mut Vec<PathBuf>) -> Result<()> {
+    let mut dir = root.to_path_buf();
+    for component in path.components() {
+        dir.push(component);
+        match fs::symlink_metadata(&dir) {
+            Ok(meta) if meta.file_type().is_symlink() => return Err(error::install_symlink(name)),
+            Ok(meta) if meta.is_dir() => {}
+            Ok(_) => return Err(error::install_exists(name)),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+                fs::create_dir(&dir).map_err(error::from)?;
+                created.push(dir.clone());
+            }
+            Err(e) => return Err(error::from(e)),
+        }
+    }
+    Ok(())
+}
+
+/// Convert the name of an archive entry into a relative path that stays inside the target
+/// directory.
+fn entry_path(name: &str) -> Result<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    let mut path = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(c) => path.push(c),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(error::install_unsafe_path(name));
+            }
+        }
+    }
+    if path.as_os_str().is_empty() || normalized.contains(':') {
+        return Err(error::install_unsafe_path(name));
+    }
+    Ok(path)
+}
+
+fn archive_error(err: ZipError) -> Error {
+    match err {
+        ZipError::Io(e) => error::from(e),
+        e => error::install_invalid_archive(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::write::{FileOptions, ZipWriter};
+
+    use super::*;
+    use crate::error::{ErrorKind, InstallError};
+
+    fn archive(entries: &[(&str, &[u8])]) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        let mut out = zip.finish().unwrap();
+        out.set_position(0);
+        out
+    }
+
+    fn tempdir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("modio-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn entry_paths() {
+        assert_eq!(entry_path("a/b.txt").unwrap(), Path::new("a/b.txt"));
+        assert_eq!(entry_path("./a/./b").unwrap(), Path::new("a/b"));
+        assert!(entry_path("../a").is_err());
+        assert!(entry_path("a/../../b").is_err());
+        assert!(entry_path("/etc/passwd").is_err());
+        assert!(entry_path("..\\a").is_err());
+        assert!(entry_path("C:\\a").is_err());
+        assert!(entry_path("").is_err());
+    }
+
+    #[test]
+    fn extract_files() {
+        let dir = tempdir("extract");
+        let zip = archive(&[("a.txt", b"hello"), ("sub/b.txt", b"world")]);
+
+        let manifest = extract(zip, &dir, &InstallOptions::default()).unwrap();
+        assert_eq!(
+            manifest.files,
+            vec![dir.join("a.txt"), dir.join("sub/b.txt")]
+        );
+        assert_eq!(manifest.size, 10);
+        assert_eq!(fs::read(dir.join("sub/b.txt")).unwrap(), b"world");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reject_archives() {
+        let dir = tempdir("reject");
+
+        let zip = archive(&[("ok.txt", b""), ("../evil.txt", b"")]);
+        let err = extract(zip, &dir, &InstallOptions::default()).unwrap_err();
+        match err.kind() {
+            ErrorKind::Install(InstallError::UnsafePath { name }) => {
+                assert_eq!(name, "../evil.txt")
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+        assert!(!dir.exists());
+
+        let zip = archive(&[("big.bin", &[0; 1024])]);
+        let options = InstallOptions::default().max_file_size(100);
+        let err = extract(zip, &dir, &options).unwrap_err();
+        match err.kind() {
+            ErrorKind::Install(InstallError::TooLarge { limit, .. }) => assert_eq!(*limit, 100),
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let zip = archive(&[("a", b""), ("b", b"")]);
+        let options = InstallOptions::default().max_entries(1);
+        assert!(extract(zip, &dir, &options).is_err());
+        // The sizes stored in the archive are too small, the limit is hit while extracting.
+        let mut zip = archive(&[("a.txt", b"hello"), ("sub/b.bin", &[0; 1024])]).into_inner();
+        let pos = zip.windows(9).rposition(|w| w == b"sub/b.bin").unwrap();
+        zip[pos - 22..pos - 18].copy_from_slice(&10u32.to_le_bytes());
+        let options = InstallOptions::default().max_size(100);
+        assert!(extract(Cursor::new(zip), &dir, &options).is_err());
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn keep_existing_files() {
+        let dir = tempdir("existing");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("user.txt"), b"mine").unwrap();
+        fs::write(dir.join("sub/b.txt"), b"mine").unwrap();
+
+        // A failing install only removes what it created.
+        let zip = archive(&[("a.txt", b"hello"), ("new/c.bin", &[0; 1024])]);
+        let options = InstallOptions::default().max_file_size(100);
+        let mut zip = zip.into_inner();
+        let pos = zip.windows(9).rposition(|w| w == b"new/c.bin").unwrap();
+        zip[pos - 22..pos - 18].copy_from_slice(&10u32.to_le_bytes());
+        assert!(extract(Cursor::new(zip), &dir, &options).is_err());
+        assert!(!dir.join("a.txt").exists());
+        assert!(!dir.join("new").exists());
+        assert_eq!(fs::read(dir.join("user.txt")).unwrap(), b"mine");
+
+        // Existing files are not overwritten.
+        let zip = archive(&[("a.txt", b"hello"), ("sub/b.txt", b"world")]);
+        let err = extract(zip, &dir, &InstallOptions::default()).unwrap_err();
+        match err.kind() {
+            ErrorKind::Install(InstallError::Exists { name }) => assert_eq!(name, "sub/b.txt"),
+            e => panic!("unexpected error: {:?}", e),
+        }
+        assert!(!dir.join("a.txt").exists());
+        assert_eq!(fs::read(dir.join("sub/b.txt")).unwrap(), b"mine");
+        assert!(dir.join("sub").is_dir());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reject_symlinks_on_disk() {
+        let dir = tempdir("symlink");
+        let outside = tempdir("symlink-outside");
+        fs::create_dir_all(&dir).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("sub")).unwrap();
+        std::os::unix::fs::symlink(outside.join("b.txt"), dir.join("b.txt")).unwrap();
+
+        let zip = archive(&[("sub/a.txt", b"hello")]);
+        let err = extract(zip, &dir, &InstallOptions::default()).unwrap_err();
+        match err.kind() {
+            ErrorKind::Install(InstallError::Symlink { name }) => assert_eq!(name, "sub/a.txt"),
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let zip = archive(&[("b.txt", b"hello")]);
+        assert!(extract(zip, &dir, &InstallOptions::default()).is_err());
+        assert_eq!(fs::read_dir(&outside).unwrap().count(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+}
@@ -10,11 +10,11 @@
 //! - Request an [API key (Read-only)](https://mod.io/apikey)
 //! - Manually create an [OAuth 2 Access Token (Read + Write)](https://mod.io/oauth)
 //! - [Email Authentication Flow](auth/struct.Auth.html#example) to create an OAuth 2 Access Token
-//! (Read + Write)
+//!   (Read + Write)
 //! - [Encrypted gog user auth ticket](auth/struct.Auth.html#method.gog_auth) to create an
-//! OAuth 2 Access Token (Read + Write)
+//!   OAuth 2 Access Token (Read + Write)
 //! - [Encrypted steam user auth ticket](auth/struct.Auth.html#method.steam_auth) to create an
-//! OAuth 2 Access Token (Read + Write)
+//!   OAuth 2 Access Token (Read + Write)
 //!
 //! # Rate Limiting
 //!
@@ -138,52 +138,70 @@
 extern crate serde_derive;
 
 use std::collections::BTreeMap;
-use std::io;
 use std::io::prelude::*;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
+use bytes::Bytes;
 use futures::{future, stream, Future as StdFuture, IntoFuture, Stream as StdStream};
 use log::{debug, log_enabled, trace};
 use mime::Mime;
 use reqwest::header::{HeaderMap, HeaderValue};
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use reqwest::header::{AUTHORIZATION, CONTENT_RANGE, CONTENT_TYPE, USER_AGENT};
 use reqwest::r#async::multipart::Form;
 use reqwest::r#async::{Client, ClientBuilder};
 use reqwest::{Method, Proxy, StatusCode};
 use serde::de::DeserializeOwned;
+use tokio_io::AsyncWrite;
 use url::Url;
 
 #[macro_use]
 mod macros;
 
+#[cfg(feature = "directory")]
+mod archive;
 pub mod auth;
+mod blocking;
+mod cache;
 #[macro_use]
 pub mod filter;
 pub mod comments;
+pub mod dependencies;
 pub mod download;
 pub mod error;
 pub mod files;
 pub mod games;
+#[cfg(feature = "install")]
+pub mod install;
 pub mod me;
 pub mod metadata;
 pub mod mods;
 mod multipart;
+pub mod registry;
 pub mod reports;
+mod search;
 pub mod teams;
+mod throttle;
 mod types;
+mod upload;
 pub mod users;
+mod validation;
 
 use crate::auth::Auth;
+use crate::cache::{Cache, CacheKey, Tee};
 use crate::comments::Comments;
 use crate::games::{GameRef, Games};
 use crate::me::Me;
 use crate::mods::{ModRef, Mods};
+use crate::multipart::FileStream;
 use crate::reports::Reports;
+use crate::throttle::Throttle;
 use crate::types::ModioMessage;
 use crate::users::Users;
 
 pub use crate::auth::Credentials;
 pub use crate::download::DownloadAction;
+use crate::download::VirusPolicy;
 pub use crate::error::{Error, Result};
 #[doc(hidden)]
 pub use crate::types::ModioErrorResponse;
@@ -204,17 +222,16 @@ pub type ModioListResponse<T> = List<T>;
 
 mod prelude {
     pub use futures::{Future as StdFuture, Stream as StdStream};
-    pub use reqwest::r#async::multipart::{Form, Part};
-    pub use reqwest::r#async::Body;
+    pub use reqwest::r#async::multipart::Form;
     pub use reqwest::StatusCode;
 
+    pub use crate::filter::any::AnyOf;
+    pub use crate::filter::endpoint::{self, Scope};
     pub use crate::filter::Filter;
     pub use crate::EntityResult;
     pub use crate::List;
     pub use crate::Modio;
     pub(crate) use crate::ModioMessage;
-    #[allow(deprecated)]
-    pub use crate::ModioResult;
     pub use crate::QueryString;
     pub(crate) use crate::RequestBody;
     pub use crate::{AddOptions, DeleteOptions, Endpoint};
@@ -247,6 +264,9 @@ struct Config {
     credentials: Credentials,
     builder: Option<ClientBuilder>,
     proxies: Vec<Proxy>,
+    download_rate: Option<u64>,
+    cache: Option<(std::path::PathBuf, u64)>,
+    virus_policy: VirusPolicy,
     #[cfg(feature = "tls")]
     tls: TlsBackend,
 }
@@ -260,6 +280,7 @@ enum TlsBackend {
 }
 
 #[cfg(feature = "tls")]
+#[allow(clippy::derivable_impls)]
 impl Default for TlsBackend {
     fn default() -> TlsBackend {
         #[cfg(feature = "default-tls")]
@@ -285,6 +306,9 @@ impl Builder {
                 credentials: credentials.into(),
                 builder: None,
                 proxies: Vec::new(),
+                download_rate: None,
+                cache: None,
+                virus_policy: Default::default(),
                 #[cfg(feature = "tls")]
                 tls: TlsBackend::default(),
             },
@@ -296,6 +320,10 @@ impl Builder {
         let config = self.config;
         let host = config.host.unwrap_or_else(|| DEFAULT_HOST.to_string());
         let credentials = config.credentials;
+        let cache = match config.cache {
+            Some((dir, max_size)) => Some(Cache::new(dir, max_size).map_err(error::from)?),
+            None => None,
+        };
 
         let client = {
             let mut builder = {
@@ -333,6 +361,9 @@ impl Builder {
             host,
             credentials,
             client,
+            throttle: Throttle::new(config.download_rate),
+            cache,
+            virus_policy: Arc::new(config.virus_policy),
         })
     }
 
@@ -373,6 +404,32 @@ impl Builder {
         self
     }
 
+    /// Limit the bandwidth of downloads to `bytes_per_sec`.
+    ///
+    /// The limit is shared by all concurrent downloads of the `Modio` client and its clones, and
+    /// can be changed later with [`Modio::set_download_rate`](struct.Modio.html#method.set_download_rate).
+    pub fn download_rate(mut self, bytes_per_sec: u64) -> Builder {
+        self.config.download_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Cache downloaded modfiles in the directory `dir`.
+    ///
+    /// Downloads of modfiles with a known md5 hash are served from the cache if the hash of the
+    /// cached file still matches. The least recently used files are removed once the cache
+    /// exceeds `max_size` bytes.
+    pub fn cache<P: Into<std::path::PathBuf>>(mut self, dir: P, max_size: u64) -> Builder {
+        self.config.cache = Some((dir.into(), max_size));
+        self
+    }
+
+    /// Set the policy for downloads of files that are not scanned or were flagged by the virus
+    /// scanner. Defaults to `VirusPolicy::Allow`.
+    pub fn virus_policy(mut self, policy: VirusPolicy) -> Builder {
+        self.config.virus_policy = policy;
+        self
+    }
+
     /// Use native TLS backend.
     #[cfg(feature = "default-tls")]
     pub fn use_default_tls(mut self) -> Builder {
@@ -394,6 +451,9 @@ pub struct Modio {
     host: String,
     client: Client,
     pub(crate) credentials: Credentials,
+    throttle: Throttle,
+    cache: Option<Cache>,
+    virus_policy: Arc<VirusPolicy>,
 }
 
 impl Modio {
@@ -430,9 +490,25 @@ impl Modio {
             host: self.host,
             client: self.client,
             credentials: credentials.into(),
+            throttle: self.throttle,
+            cache: self.cache,
+            virus_policy: self.virus_policy,
         }
     }
 
+    /// Return the current bandwidth limit for downloads in bytes per second.
+    pub fn download_rate(&self) -> Option<u64> {
+        self.throttle.rate()
+    }
+
+    /// Set or remove the bandwidth limit for downloads in bytes per second.
+    ///
+    /// The new limit applies immediately to running downloads and is shared by all clones of
+    /// this client.
+    pub fn set_download_rate(&self, bytes_per_sec: Option<u64>) {
+        self.throttle.set_rate(bytes_per_sec);
+    }
+
     /// Return a reference to an interface for requesting access tokens.
     pub fn auth(&self) -> Auth {
         Auth::new(self.clone())
@@ -505,15 +581,208 @@ impl Modio {
     where
         A: Into<DownloadAction>,
         W: Write + 'static + Send,
+    {
+        Box::new(
+            self.download_stream(action)
+                .fold((0, w), |(len, mut out), chunk| {
+                    out.write_all(&chunk)
+                        .map(|_| (len + chunk.len() as u64, out))
+                        .map_err(error::from)
+                }),
+        )
+    }
+
+    /// Performs a download into an asynchronous writer.
+    ///
+    /// The writer is flushed after the download is complete. Fails with the same errors as
+    /// [`Modio::download`](struct.Modio.html#method.download).
+    /// # Example
+    /// ```no_run
+    /// use std::fs::File;
+    ///
+    /// use modio::{Credentials, DownloadAction, Error, Modio};
+    /// use tokio::runtime::Runtime;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let mut rt = Runtime::new().expect("new rt");
+    ///     let modio = Modio::new(
+    ///         Credentials::ApiKey(String::from("user-or-game-api-key")),
+    ///     )?;
+    ///
+    ///     let action = DownloadAction::Primary {
+    ///         game_id: 5,
+    ///         mod_id: 19,
+    ///     };
+    ///     let out = File::create("mod.zip").expect("new file");
+    ///     let out = tokio::fs::File::from_std(out);
+    ///     let (len, out) = rt.block_on(modio.download_async(action, out))?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn download_async<A, W>(&self, action: A, w: W) -> Future<(u64, W)>
+    where
+        A: Into<DownloadAction>,
+        W: AsyncWrite + 'static + Send,
+    {
+        Box::new(
+            self.download_stream(action)
+                .fold((0, w), |(len, out), chunk| {
+                    tokio_io::io::write_all(out, chunk)
+                        .map(move |(out, chunk)| (len + chunk.len() as u64, out))
+                        .map_err(error::from)
+                })
+                .and_then(|(len, out)| {
+                    tokio_io::io::flush(out)
+                        .map(move |out| (len, out))
+                        .map_err(error::from)
+                }),
+        )
+    }
+
+    /// Provides a stream over the bytes of a download.
+    ///
+    /// Fails with the same errors as [`Modio::download`](struct.Modio.html#method.download).
+    /// # Example
+    /// ```no_run
+    /// use modio::{Credentials, DownloadAction, Error, Modio};
+    /// use tokio::prelude::*;
+    /// use tokio::runtime::Runtime;
+    ///
+    /// fn main() -> Result<(), Error> {
+    ///     let mut rt = Runtime::new().expect("new rt");
+    ///     let modio = Modio::new(
+    ///         Credentials::ApiKey(String::from("user-or-game-api-key")),
+    ///     )?;
+    ///
+    ///     let action = DownloadAction::Primary {
+    ///         game_id: 5,
+    ///         mod_id: 19,
+    ///     };
+    ///     let task = modio
+    ///         .download_stream(action)
+    ///         .fold(md5::Context::new(), |mut ctx, chunk| {
+    ///             ctx.consume(&chunk);
+    ///             Ok::<_, Error>(ctx)
+    ///         });
+    ///     let ctx = rt.block_on(task)?;
+    ///     println!("md5: {:x}", ctx.compute());
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn download_stream<A>(&self, action: A) -> Stream<Bytes>
+    where
+        A: Into<DownloadAction>,
     {
         let instance = self.clone();
+        Box::new(
+            self.resolve(action)
+                .and_then(move |resolved| instance.open_download(resolved))
+                .flatten_stream(),
+        )
+    }
+
+    /// Apply the virus policy and serve the download from the cache if possible.
+    fn open_download(&self, resolved: download::Resolved) -> Future<Stream<Bytes>> {
+        let download::Resolved { url, file, .. } = resolved;
+        if let Some(ref file) = file {
+            if !self.virus_policy.check(file) {
+                return future_err!(error::download_unsafe_file(file));
+            }
+        }
+        let key = file.and_then(|f| CacheKey::new(f.id, &f.filehash.md5));
+        let (cache, key) = match (self.cache.clone(), key) {
+            (Some(cache), Some(key)) => (cache, key),
+            _ => return Box::new(future::ok(self.request_file(url))),
+        };
+        let instance = self.clone();
+        Box::new(cache.get(&key).map(move |hit| -> Stream<Bytes> {
+            match hit {
+                Some(path) => Box::new(FileStream::open(path).map_err(error::from)),
+                None => {
+                    let stream = instance.request_file(url);
+                    Box::new(Tee::new(stream, cache.writer(key)))
+                }
+            }
+        }))
+    }
+
+    /// Downloads a mod archive and extracts it into the directory `dir`.
+    ///
+    /// The archive is downloaded into a temporary file which is removed afterwards. See the
+    /// [`install`](install/index.html) module for the checks done before extracting. The
+    /// extraction runs on a separate thread and the files and directories it created are removed
+    /// if it fails.
+    ///
+    /// Like [`download_async`](#method.download_async) the returned future must run on the
+    /// default tokio runtime. Requires the `install` feature.
+    #[cfg(feature = "install")]
+    pub fn install<A, P>(
+        &self,
+        action: A,
+        dir: P,
+        options: install::InstallOptions,
+    ) -> Future<install::Manifest>
+    where
+        A: Into<DownloadAction>,
+        P: AsRef<std::path::Path>,
+    {
+        use std::io::{Seek, SeekFrom};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let dir = dir.as_ref().to_path_buf();
+        let tmp = std::env::temp_dir().join(format!(
+            "modio-install-{}-{}.zip",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+        ));
+        let file = match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&tmp)
+        {
+            Ok(file) => file,
+            Err(e) => return future_err!(error::from(e)),
+        };
+        let file = tokio_fs::File::from_std(file);
+
+        Box::new(
+            self.download_async(action, file)
+                .and_then(move |(_, file)| {
+                    let mut file = file.into_std();
+                    blocking::run(move || {
+                        file.seek(SeekFrom::Start(0)).map_err(error::from)?;
+                        install::extract(file, dir, &options)
+                    })
+                })
+                .then(move |ret| {
+                    let _ = std::fs::remove_file(tmp);
+                    ret
+                }),
+        )
+    }
+
+    /// Resolves the url and, if known, the modfile to download for a `DownloadAction` without
+    /// downloading it.
+    ///
+    /// Fails with the same errors as [`Modio::download`](struct.Modio.html#method.download).
+    /// For `DownloadAction::VersionReq` the files whose version can't be parsed as semver are
+    /// returned in [`Resolved::skipped`](download/struct.Resolved.html#structfield.skipped).
+    pub fn resolve<A>(&self, action: A) -> Future<download::Resolved>
+    where
+        A: Into<DownloadAction>,
+    {
+        use download::Resolved;
+
         match action.into() {
             DownloadAction::Primary { game_id, mod_id } => {
                 Box::new(self.mod_(game_id, mod_id).get().and_then(move |m| {
                     if let Some(file) = m.modfile {
-                        instance.request_file(&file.download.binary_url.to_string(), w)
+                        Ok(Resolved::new(file.download.binary_url.clone(), Some(file)))
                     } else {
-                        future_err!(error::download_no_primary(game_id, mod_id))
+                        Err(error::download_no_primary(game_id, mod_id))
                     }
                 }))
             }
@@ -525,9 +794,7 @@ impl Modio {
                 self.mod_(game_id, mod_id)
                     .file(file_id)
                     .get()
-                    .and_then(move |file| {
-                        instance.request_file(&file.download.binary_url.to_string(), w)
-                    })
+                    .map(|file| Resolved::new(file.download.binary_url.clone(), Some(file)))
                     .map_err(move |e| match e.kind() {
                         error::ErrorKind::Fault {
                             code: StatusCode::NOT_FOUND,
@@ -545,41 +812,86 @@ impl Modio {
                 use files::filters::{DateAdded, Version};
                 use filter::prelude::*;
 
-                let filter = Version::eq(version.clone())
-                    .order_by(DateAdded::desc())
-                    .limit(2);
+                let filter = Version::eq(version.clone()).order_by(DateAdded::desc());
 
                 Box::new(
                     self.mod_(game_id, mod_id)
                         .files()
-                        .list(&filter)
-                        .and_then(move |list| {
-                            use crate::download::ResolvePolicy::*;
-
-                            let (file, error) = match (list.count, policy) {
-                                (0, _) => (
-                                    None,
-                                    Some(error::download_version_not_found(
-                                        game_id, mod_id, version,
-                                    )),
-                                ),
-                                (1, _) => (Some(&list[0]), None),
-                                (_, Latest) => (Some(&list[0]), None),
-                                (_, Fail) => (
-                                    None,
-                                    Some(error::download_multiple_files(game_id, mod_id, version)),
-                                ),
-                            };
+                        .iter(&filter)
+                        .collect()
+                        .and_then(move |mut files| {
+                            if files.is_empty() {
+                                return Err(error::download_version_not_found(
+                                    game_id, mod_id, version,
+                                ));
+                            }
+                            match policy.select(&files) {
+                                Some(i) => {
+                                    let file = files.swap_remove(i);
+                                    Ok(Resolved::new(file.download.binary_url.clone(), Some(file)))
+                                }
+                                None => {
+                                    Err(error::download_multiple_files(game_id, mod_id, version))
+                                }
+                            }
+                        }),
+                )
+            }
+            #[cfg(feature = "semver")]
+            DownloadAction::VersionReq {
+                game_id,
+                mod_id,
+                req,
+            } => {
+                use files::filters::DateAdded;
+                use filter::prelude::*;
 
-                            if let Some(file) = file {
-                                instance.request_file(&file.download.binary_url.to_string(), w)
-                            } else {
-                                future_err!(error.expect("bug in previous match!"))
+                Box::new(
+                    self.mod_(game_id, mod_id)
+                        .files()
+                        .iter(&DateAdded::asc())
+                        .collect()
+                        .and_then(move |files| {
+                            let versions = files.iter().map(|f| f.version.as_deref());
+                            let (best, invalid) = download::select_version(versions, &req);
+                            let best = match best {
+                                Some(best) => best,
+                                None => {
+                                    let invalid = invalid
+                                        .into_iter()
+                                        .filter_map(|i| files[i].version.clone())
+                                        .collect();
+                                    return Err(error::download_no_matching_version(
+                                        game_id,
+                                        mod_id,
+                                        req.to_string(),
+                                        invalid,
+                                    ));
+                                }
+                            };
+                            let mut file = None;
+                            let mut skipped = Vec::new();
+                            for (i, f) in files.into_iter().enumerate() {
+                                if i == best {
+                                    file = Some(f);
+                                } else if invalid.contains(&i) {
+                                    skipped.push(f);
+                                }
                             }
+                            let file = file.expect("bug in select_version!");
+                            Ok(Resolved {
+                                url: file.download.binary_url.clone(),
+                                file: Some(file),
+                                skipped,
+                            })
                         }),
                 )
             }
-            DownloadAction::Url(url) => self.request_file(&url.to_string(), w),
+            DownloadAction::FileObj(file) => {
+                let url = file.download.binary_url.clone();
+                Box::new(future::ok(Resolved::new(url, Some(*file))))
+            }
+            DownloadAction::Url(url) => Box::new(future::ok(Resolved::new(url, None))),
         }
     }
 
@@ -605,7 +917,7 @@ impl Modio {
         Out: DeserializeOwned + 'static + Send,
     {
         let url = if let Credentials::ApiKey(ref api_key) = self.credentials {
-            Url::parse(&uri)
+            Url::parse(uri)
                 .map(|mut url| {
                     url.query_pairs_mut().append_pair("api_key", api_key);
                     url
@@ -638,6 +950,13 @@ impl Modio {
                     trace!("{:?}", form);
                     req = req.multipart(form);
                 }
+                RequestBody::Chunk(data, range) => {
+                    trace!("chunk: {}", range);
+                    req = req
+                        .header(CONTENT_RANGE, &*range)
+                        .header(CONTENT_TYPE, mime::APPLICATION_OCTET_STREAM.as_ref())
+                        .body(data);
+                }
                 _ => {}
             }
             req.send()
@@ -677,7 +996,7 @@ impl Modio {
                                 .map_err(error::from)
                         } else {
                             match (remaining, reset) {
-                                (Some(remaining), Some(reset)) if remaining == 0 => {
+                                (Some(0), Some(reset)) => {
                                     debug!("ratelimit reached: reset in {} mins", reset);
                                     Err(error::ratelimit(reset))
                                 }
@@ -699,33 +1018,23 @@ impl Modio {
         Box::new(self.request(method, uri, body).map(|(_, entity)| entity))
     }
 
-    fn request_file<W>(&self, uri: &str, out: W) -> Future<(u64, W)>
-    where
-        W: Write + 'static + Send,
-    {
-        debug!("downloading file: {}", uri);
-        let url = Url::parse(uri).map_err(error::from).into_future();
+    fn request_file(&self, url: Url) -> Stream<Bytes> {
+        debug!("downloading file: {}", url);
 
-        let instance = self.clone();
-        let response = url.and_then(move |url| {
-            instance
-                .client
+        let throttle = self.throttle.clone();
+        Box::new(
+            self.client
                 .request(Method::GET, url.as_str())
                 .send()
                 .map_err(error::from)
-        });
-
-        Box::new(response.and_then(move |response| {
-            Box::new(response.into_body().map_err(error::from).fold(
-                (0, out),
-                |(len, mut out), chunk| {
-                    io::copy(&mut io::Cursor::new(&chunk), &mut out)
-                        .map(|n| (n + len, out))
-                        .map_err(error::from)
-                        .into_future()
-                },
-            ))
-        }))
+                .map(move |response| {
+                    let body = response.into_body().map_err(error::from);
+                    throttle
+                        .stream(body)
+                        .map(|chunk| Bytes::from(hyper::Chunk::from(chunk)))
+                })
+                .flatten_stream(),
+        )
     }
 
     fn stream<D>(&self, uri: &str) -> Stream<D>
@@ -786,7 +1095,7 @@ impl Modio {
                                     instance
                                         .request::<_, List<D>>(
                                             Method::GET,
-                                            &state.url.to_string(),
+                                            state.url.as_ref(),
                                             RequestBody::Empty,
                                         )
                                         .map(move |(url, list)| {
@@ -813,6 +1122,24 @@ impl Modio {
         )
     }
 
+    /// Stream over the merged results of the alternatives of an `AnyOf` filter.
+    fn stream_any<E, D>(&self, path: &str, filter: &filter::any::AnyOf<E>) -> Stream<D>
+    where
+        D: DeserializeOwned + filter::matches::Entity + 'static + Send,
+    {
+        let streams = filter
+            .queries()
+            .map(|query| {
+                if query.is_empty() {
+                    self.stream(path)
+                } else {
+                    self.stream(&format!("{}?{}", path, query))
+                }
+            })
+            .collect();
+        filter.merge(streams)
+    }
+
     fn get<D>(&self, uri: &str) -> Future<D>
     where
         D: DeserializeOwned + 'static + Send,
@@ -878,6 +1205,8 @@ pub(crate) enum RequestBody {
     Empty,
     Body(String, Option<Mime>),
     Form(Form),
+    /// Part of a multipart upload session with its `Content-Range`.
+    Chunk(Bytes, String),
 }
 
 impl From<String> for RequestBody {
@@ -948,7 +1277,7 @@ where
 mod private {
     pub trait Sealed {}
 
-    impl Sealed for super::filter::Filter {}
+    impl<E> Sealed for super::filter::Filter<E> {}
     impl Sealed for super::files::EditFileOptions {}
     impl Sealed for super::games::AddTagsOptions {}
     impl Sealed for super::games::EditGameOptions {}
@@ -972,3 +1301,204 @@ pub trait DeleteOptions: private::Sealed {}
 pub trait QueryString: private::Sealed {
     fn to_query_string(&self) -> String;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use tokio::runtime::Runtime;
+
+    use super::*;
+
+    /// Serve `body` for every request on a local port. Returns the url and the request counter.
+    pub(crate) fn serve<B: Into<Vec<u8>>>(body: B) -> (Url, Arc<AtomicUsize>) {
+        serve_pages(vec![body])
+    }
+
+    /// Serve the bodies in order, the last one for all further requests.
+    pub(crate) fn serve_pages<B: Into<Vec<u8>>>(bodies: Vec<B>) -> (Url, Arc<AtomicUsize>) {
+        let bodies = bodies.into_iter().map(Into::into).collect::<Vec<Vec<u8>>>();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/mod.zip", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => return,
+                };
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let body = &bodies[n.min(bodies.len() - 1)];
+                let mut buf = [0; 4096];
+                let _ = stream.read(&mut buf);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(body);
+            }
+        });
+        (url.parse().unwrap(), hits)
+    }
+
+    pub(crate) fn modio() -> Modio {
+        Modio::new(Credentials::ApiKey(String::new())).unwrap()
+    }
+
+    /// A modfile downloaded from `url` as returned by the API.
+    pub(crate) fn file(id: u32, version: &str, url: &Url) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "mod_id": 2, "date_added": id, "date_scanned": id, "virus_status": 1,
+            "virus_positive": 0, "virustotal_hash": null, "filesize": 11,
+            "filehash": {"md5": "5eb63bbbe01eeed093cb22bb8f5acdc3"},
+            "filename": format!("mod-{}.zip", version), "version": version, "changelog": null,
+            "metadata_blob": null, "download": {"binary_url": url, "date_expires": 0},
+        })
+    }
+
+    /// A user as returned by the API.
+    pub(crate) fn user(id: u32, username: &str) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "name_id": username.to_lowercase(), "username": username,
+            "date_online": id, "avatar": {}, "timezone": "", "language": "",
+            "profile_url": "https://mod.io/members/foo",
+        })
+    }
+
+    /// A mod with the `(downloads, weighted rating, subscribers)` stats, tags and metadata.
+    pub(crate) fn mod_(
+        id: u32,
+        name: &str,
+        stats: (u32, f64, u32),
+        tags: &[&str],
+        kvp: &[(&str, &str)],
+    ) -> mods::Mod {
+        let (downloads, rating, subscribers) = stats;
+        let tags: Vec<_> = tags
+            .iter()
+            .map(|t| serde_json::json!({"name": t, "date_added": 1}))
+            .collect();
+        let kvp: Vec<_> = kvp
+            .iter()
+            .map(|(k, v)| serde_json::json!({"metakey": k, "metavalue": v}))
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "id": id, "game_id": 1, "status": 1, "visible": 1, "maturity_option": 0,
+            "submitted_by": user(3, "Foo"),
+            "date_added": id, "date_updated": id, "date_live": id,
+            "logo": {
+                "filename": "logo.png",
+                "original": "https://example.com/logo.png",
+                "thumb_320x180": "https://example.com/320.png",
+                "thumb_640x360": "https://example.com/640.png",
+                "thumb_1280x720": "https://example.com/1280.png",
+            },
+            "homepage_url": null, "name": name, "name_id": name.to_lowercase(), "summary": "",
+            "description": null, "description_plaintext": null, "metadata_blob": null,
+            "profile_url": "https://mod.io/g/foo/m/foo", "modfile": {}, "media": {},
+            "metadata_kvp": kvp,
+            "tags": tags,
+            "stats": {
+                "mod_id": id, "downloads_total": downloads, "subscribers_total": subscribers,
+                "popularity_rank_position": id, "popularity_rank_total_mods": 3,
+                "ratings_total": 0, "ratings_positive": 0, "ratings_negative": 0,
+                "ratings_percentage_positive": 0, "ratings_weighted_aggregate": rating,
+                "ratings_display_text": "", "date_expires": 100,
+            },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn download_stream() {
+        let (url, hits) = serve(b"hello world");
+        let mut rt = Runtime::new().unwrap();
+
+        let chunks = rt.block_on(modio().download_stream(url).collect()).unwrap();
+        assert_eq!(chunks.concat(), b"hello world");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn download_async() {
+        let (url, _) = serve(b"hello world");
+        let mut rt = Runtime::new().unwrap();
+
+        let out = std::io::Cursor::new(Vec::new());
+        let (len, out) = rt.block_on(modio().download_async(url, out)).unwrap();
+        assert_eq!(len, 11);
+        assert_eq!(out.into_inner(), b"hello world");
+    }
+    #[cfg(feature = "semver")]
+    #[test]
+    fn resolve_reports_skipped_versions() {
+        let url: Url = "https://example.com/mod.zip".parse().unwrap();
+        let files = vec![
+            file(1, "1.0", &url),
+            file(2, "final", &url),
+            file(3, "v1.2", &url),
+        ];
+        let list = serde_json::json!({
+            "data": files,
+            "result_count": 3, "result_total": 3, "result_limit": 100, "result_offset": 0,
+        });
+        let (host, _) = serve(list.to_string());
+        let modio = Modio::host(host.as_str(), Credentials::ApiKey(String::new())).unwrap();
+        let mut rt = Runtime::new().unwrap();
+
+        let action = DownloadAction::VersionReq {
+            game_id: 1,
+            mod_id: 2,
+            req: semver::VersionReq::parse("^1").unwrap(),
+        };
+        let resolved = rt.block_on(modio.resolve(action)).unwrap();
+        assert_eq!(resolved.file.map(|f| f.id), Some(3));
+        let skipped = resolved.skipped.iter().map(|f| f.id).collect::<Vec<_>>();
+        assert_eq!(skipped, [2]);
+    }
+    #[test]
+    fn download_file_obj() {
+        use crate::download::VirusPolicy;
+        use crate::error::{DownloadError, ErrorKind};
+
+        let (url, hits) = serve(b"hello world");
+        let dir = std::env::temp_dir().join(format!("modio-download-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let modio = Modio::builder(Credentials::ApiKey(String::new()))
+            .cache(&dir, 1024)
+            .virus_policy(VirusPolicy::Deny)
+            .build()
+            .unwrap();
+        let mut rt = Runtime::new().unwrap();
+        let download = |file: serde_json::Value| {
+            let file: files::File = serde_json::from_value(file).unwrap();
+            modio.download_stream(file).concat2()
+        };
+
+        // The second download is served from the cache.
+        let data = rt.block_on(download(file(1, "1.0", &url))).unwrap();
+        assert_eq!(&data[..], b"hello world");
+        let data = rt.block_on(download(file(1, "1.0", &url))).unwrap();
+        assert_eq!(&data[..], b"hello world");
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        let mut flagged = file(2, "1.1", &url);
+        flagged["virus_positive"] = 1.into();
+        let err = rt.block_on(download(flagged)).unwrap_err();
+        match err.kind() {
+            ErrorKind::Download(DownloadError::UnsafeFile { file_id, .. }) => {
+                assert_eq!(*file_id, 2)
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
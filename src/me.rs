@@ -47,7 +47,7 @@ impl Me {
     /// [required: token]
     ///
     /// See [Filters and sorting](filters/events/index.html).
-    pub fn events(&self, filter: &Filter) -> Stream<Event> {
+    pub fn events(&self, filter: &Filter<impl Scope<endpoint::UserEvents>>) -> Stream<Event> {
         token_required!(s self.modio);
         let mut uri = vec!["/me/events".to_owned()];
         let query = filter.to_query_string();
@@ -60,7 +60,7 @@ impl Me {
     /// Provides a stream over all mod's the authenticated user is subscribed to. [required: token]
     ///
     /// See [Filters and sorting](filters/subscriptions/index.html).
-    pub fn subscriptions(&self, filter: &Filter) -> Stream<Mod> {
+    pub fn subscriptions(&self, filter: &Filter<impl Scope<endpoint::Mods>>) -> Stream<Mod> {
         token_required!(s self.modio);
         let mut uri = vec!["/me/subscribed".to_owned()];
         let query = filter.to_query_string();
@@ -74,7 +74,7 @@ impl Me {
     /// token]
     ///
     /// See [Filters and sorting](filters/ratings/index.html).
-    pub fn ratings(&self, filter: &Filter) -> Stream<Rating> {
+    pub fn ratings(&self, filter: &Filter<impl Scope<endpoint::Ratings>>) -> Stream<Rating> {
         token_required!(s self.modio);
         let mut uri = vec!["/me/ratings".to_owned()];
         let query = filter.to_query_string();
@@ -111,7 +111,7 @@ pub mod filters {
     /// ```
     /// use modio::filter::prelude::*;
     /// use modio::me::filters::events::EventType as Filter;
-    /// use modio::mods::EventType;
+    /// use modio::me::EventType;
     ///
     /// let filter = Id::gt(1024).and(Filter::eq(EventType::ModfileChanged));
     /// ```
@@ -123,12 +123,21 @@ pub mod filters {
         #[doc(inline)]
         pub use crate::filter::prelude::DateAdded;
 
-        #[doc(inline)]
-        pub use crate::mods::filters::events::UserId;
-        #[doc(inline)]
-        pub use crate::mods::filters::events::EventType;
+        type Endpoint = crate::filter::endpoint::UserEvents;
 
-        filter!(GameId, GAME_ID, "game_id", Eq, NotEq, In, Cmp, OrderBy);
+        filter!(GameId, GAME_ID, "game_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(UserId, USER_ID, "user_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(EventType, EVENT_TYPE, "event_type", crate::types::EventType: Eq, NotEq, In, OrderBy);
+
+        /// The user event fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+        pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+            Id::FIELD,
+            GameId::FIELD,
+            ModId::FIELD,
+            UserId::FIELD,
+            DateAdded::FIELD,
+            EventType::FIELD,
+        ]);
     }
 
     /// Subscriptions filters and sorting.
@@ -223,6 +232,35 @@ pub mod filters {
         pub use crate::mods::filters::Ratings;
         #[doc(inline)]
         pub use crate::mods::filters::Subscribers;
+
+        type Endpoint = crate::filter::endpoint::Mods;
+
+        /// The subscription fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+        pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+            Fulltext::FIELD,
+            Id::FIELD,
+            Name::FIELD,
+            NameId::FIELD,
+            GameId::FIELD,
+            Status::FIELD,
+            Visible::FIELD,
+            SubmittedBy::FIELD,
+            DateAdded::FIELD,
+            DateUpdated::FIELD,
+            DateLive::FIELD,
+            MaturityOption::FIELD,
+            Summary::FIELD,
+            Description::FIELD,
+            Homepage::FIELD,
+            Modfile::FIELD,
+            MetadataBlob::FIELD,
+            MetadataKVP::FIELD,
+            Tags::FIELD,
+            Downloads::FIELD,
+            Popular::FIELD,
+            Ratings::FIELD,
+            Subscribers::FIELD,
+        ]);
     }
 
     /// Rating filters and sorting.
@@ -261,16 +299,26 @@ pub mod filters {
         #[doc(inline)]
         pub use crate::filter::prelude::ModId;
 
-        filter!(GameId, GAME_ID, "game_id", Eq, NotEq, In, Cmp, OrderBy);
-        filter!(Rating, RATING, "rating", Eq, NotEq, In, Cmp, OrderBy);
-        filter!(DateAdded, DATE_ADDED, "date_added", Eq, NotEq, In, Cmp, OrderBy);
+        type Endpoint = crate::filter::endpoint::Ratings;
+
+        filter!(GameId, GAME_ID, "game_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(Rating, RATING, "rating", i8: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(DateAdded, DATE_ADDED, "date_added", crate::filter::Timestamp: Eq, NotEq, In, Cmp, OrderBy);
+
+        /// The rating fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+        pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+            GameId::FIELD,
+            ModId::FIELD,
+            Rating::FIELD,
+            DateAdded::FIELD,
+        ]);
 
         impl Rating {
-            pub fn positive() -> Filter {
+            pub fn positive() -> Filter<Endpoint> {
                 Rating::eq(1)
             }
 
-            pub fn negative() -> Filter {
+            pub fn negative() -> Filter<Endpoint> {
                 Rating::eq(-1)
             }
         }
@@ -2,9 +2,10 @@
 use futures::future;
 use url::form_urlencoded;
 
-use crate::error::Error;
+use crate::error::{Error, Result};
 use crate::prelude::*;
 use crate::types::mods::MetadataMap;
+use crate::validation::Validator;
 
 pub struct Metadata {
     modio: Modio,
@@ -46,8 +47,13 @@ impl Metadata {
     }
 
     /// Add metadata for a mod that this `Metadata` refers to.
+    ///
+    /// The keys can't contain `:` because mod.io splits the `key:value` pairs at the first `:`.
     pub fn add(&self, metadata: &MetadataMap) -> Future<()> {
         token_required!(self.modio);
+        if let Err(e) = validate(metadata) {
+            return future_err!(e);
+        }
         Box::new(
             self.modio
                 .post::<ModioMessage, _>(&self.path(), metadata.to_query_string())
@@ -58,6 +64,9 @@ impl Metadata {
     /// Delete metadata for a mod that this `Metadata` refers to.
     pub fn delete(&self, metadata: &MetadataMap) -> Future<()> {
         token_required!(self.modio);
+        if let Err(e) = validate(metadata) {
+            return future_err!(e);
+        }
         self.modio.delete(&self.path(), metadata.to_query_string())
     }
 }
@@ -70,9 +79,25 @@ impl QueryString for MetadataMap {
                 ser.append_pair("metadata[]", k);
             }
             for v in vals {
-                ser.append_pair("metadata[]", &format!("{}:{}", k, v));
+                ser.append_pair("metadata[]", &encode_kvp(k, v));
             }
         }
         ser.finish()
     }
 }
+
+/// Encode a metadata key value pair as `key:value`.
+///
+/// mod.io splits the pairs at the first `:` and has no escape sequences, the key must not
+/// contain a `:`.
+pub(crate) fn encode_kvp(key: &str, value: &str) -> String {
+    format!("{}:{}", key, value)
+}
+
+fn validate(metadata: &MetadataMap) -> Result<()> {
+    let mut v = Validator::new();
+    for key in metadata.keys() {
+        v.reserved("metadata", key, ":");
+    }
+    v.finish()
+}
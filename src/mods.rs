@@ -1,18 +1,24 @@
 //! Mods Interface
 use std::ffi::OsStr;
 use std::path::Path;
+use std::sync::Arc;
 
+use futures::future;
 use mime::{APPLICATION_OCTET_STREAM, IMAGE_STAR};
 use url::{form_urlencoded, Url};
 
-use crate::error::ErrorKind;
+use crate::dependencies::InstallPlan;
+use crate::error::{ErrorKind, Result};
 use crate::files::{FileRef, Files};
+use crate::games::TagOption;
 use crate::metadata::Metadata;
-use crate::multipart::{FileSource, FileStream};
+use crate::multipart::{FileSource, OnProgress};
 use crate::prelude::*;
 use crate::teams::Members;
+use crate::validation::{self, Validator};
 use crate::Comments;
 
+pub use crate::multipart::Progress;
 pub use crate::types::mods::{
     Dependency, Event, EventType, Image, MaturityOption, Media, MetadataMap, Mod, Popularity,
     Ratings, Statistics, Tag, Visibility,
@@ -33,7 +39,7 @@ impl MyMods {
     /// List all mods the authenticated user added or is team member of. [required: token]
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<Mod>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Mods>>) -> Future<List<Mod>> {
         token_required!(self.modio);
         let mut uri = vec!["/me/mods".to_owned()];
         let query = filter.to_query_string();
@@ -47,7 +53,7 @@ impl MyMods {
     /// token]
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<Mod> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Mods>>) -> Stream<Mod> {
         token_required!(s self.modio);
         let mut uri = vec!["/me/mods".to_owned()];
         let query = filter.to_query_string();
@@ -81,7 +87,7 @@ impl Mods where {
     /// List all mods.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<Mod>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Mods>>) -> Future<List<Mod>> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -93,7 +99,7 @@ impl Mods where {
     /// Provides a stream over all mods of the game.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<Mod> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Mods>>) -> Stream<Mod> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -102,16 +108,76 @@ impl Mods where {
         self.modio.stream(&uri.join("?"))
     }
 
+    /// Provides a stream over all mods of the game that pass any of the alternatives.
+    ///
+    /// See [OR-queries](../filter/any/index.html).
+    pub fn iter_any(&self, filter: &AnyOf<impl Scope<endpoint::Mods>>) -> Stream<Mod> {
+        self.modio.stream_any(&self.path(""), filter)
+    }
+
+    /// Search mods by the fulltext search, their names and tags and rank the results by their
+    /// relevance, popularity and ratings.
+    ///
+    /// The query words are also matched with typos against the mod names. The candidates of
+    /// the fulltext search, the name and the tag matches are requested separately, each capped
+    /// at [`SearchOptions::candidates`], and ranked locally. The results of the fulltext search
+    /// are always part of the ranking.
+    ///
+    /// `List::total` is the number of ranked candidates, not the number of all matching mods.
+    /// Every call requests the candidates again, use a larger `limit` to page through the
+    /// results locally.
+    ///
+    /// ```no_run
+    /// use modio::mods::SearchOptions;
+    /// # use modio::{Credentials, Modio};
+    /// # let modio = Modio::new(Credentials::ApiKey(String::new()))?;
+    ///
+    /// let opts = SearchOptions::new("castel map").limit(10);
+    /// let mods = modio.game(51).mods().search(opts);
+    /// # Ok::<_, modio::Error>(())
+    /// ```
+    pub fn search(&self, options: SearchOptions) -> Future<List<Mod>> {
+        let candidates = options
+            .candidates
+            .unwrap_or(crate::search::DEFAULT_CANDIDATES);
+        let requests = crate::search::filters(&options)
+            .iter()
+            .map(|f| self.iter(f).take(candidates as u64).collect())
+            .collect::<Vec<_>>();
+        Box::new(
+            future::join_all(requests)
+                .map(move |candidates| crate::search::rank(&options, candidates)),
+        )
+    }
+
     /// Add a mod and return the newly created Modio mod object. [required: token]
+    ///
+    /// The options are validated before the logo is uploaded. If tags are set, the tag options
+    /// of the game are requested first to check the tags.
     pub fn add(&self, options: AddModOptions) -> Future<Mod> {
         token_required!(self.modio);
-        self.modio.post_form(&self.path(""), options)
+        if options.tags.is_none() {
+            if let Err(e) = options.validate() {
+                return future_err!(e);
+            }
+            return self.modio.post_form(&self.path(""), options);
+        }
+        let modio = self.modio.clone();
+        let path = self.path("");
+        let tags = self.modio.game(self.game).tags().iter().collect();
+        Box::new(tags.and_then(move |tags| {
+            futures::future::result(options.validate_with_tags(&tags))
+                .and_then(move |_| modio.post_form(&path, options))
+        }))
     }
 
     /// Provides a stream over the statistics for all mods of a game.
     ///
     /// See [Filters and sorting](filters/stats/index.html).
-    pub fn statistics(&self, filter: &Filter) -> Stream<Statistics> {
+    pub fn statistics(
+        &self,
+        filter: &Filter<impl Scope<endpoint::ModStats>>,
+    ) -> Stream<Statistics> {
         let mut uri = vec![self.path("/stats")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -123,7 +189,7 @@ impl Mods where {
     /// Provides a stream over the event log for all mods of a game sorted by latest event first.
     ///
     /// See [Filters and sorting](filters/events/index.html).
-    pub fn events(&self, filter: &Filter) -> Stream<Event> {
+    pub fn events(&self, filter: &Filter<impl Scope<endpoint::ModEvents>>) -> Stream<Event> {
         let mut uri = vec![self.path("/events")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -184,6 +250,14 @@ impl ModRef {
         Endpoint::new(self.modio.clone(), self.path("/dependencies"))
     }
 
+    /// Resolve the dependencies of a mod transitively and return them as install plan.
+    ///
+    /// Fails if the dependencies form a cycle or if a dependency is missing, not accepted, hidden
+    /// or has no primary file. See [`dependencies`](../dependencies/index.html).
+    pub fn resolve_dependencies(&self) -> Future<InstallPlan> {
+        crate::dependencies::resolve(self.modio.clone(), self.game, self.id)
+    }
+
     /// Return the statistics for a mod.
     pub fn statistics(&self) -> Future<Statistics> {
         self.modio.get(&self.path("/stats"))
@@ -192,7 +266,7 @@ impl ModRef {
     /// Provides a stream over the event log for a mod sorted by latest event first.
     ///
     /// See [Filters and sorting](filters/events/index.html).
-    pub fn events(&self, filter: &Filter) -> Stream<Event> {
+    pub fn events(&self, filter: &Filter<impl Scope<endpoint::ModEvents>>) -> Stream<Event> {
         let mut uri = vec![self.path("/events")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -216,6 +290,9 @@ impl ModRef {
     /// Add new media to a mod. [required: token]
     pub fn add_media(&self, options: AddMediaOptions) -> Future<()> {
         token_required!(self.modio);
+        if let Err(e) = options.validate() {
+            return future_err!(e);
+        }
         Box::new(
             self.modio
                 .post_form::<ModioMessage, _>(&self.path("/media"), options)
@@ -331,6 +408,11 @@ impl ModRef {
 /// ```
 #[rustfmt::skip]
 pub mod filters {
+    use crate::filter::{Filter, IntoValues, OneOrMany, Operator};
+    use crate::metadata::encode_kvp;
+    use crate::validation::Validator;
+    use crate::Result;
+
     #[doc(inline)]
     pub use crate::filter::prelude::Fulltext;
     #[doc(inline)]
@@ -350,21 +432,125 @@ pub mod filters {
     #[doc(inline)]
     pub use crate::filter::prelude::SubmittedBy;
 
-    filter!(GameId, GAME_ID, "game_id", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(Visible, VISIBLE, "visible", Eq);
-    filter!(MaturityOption, MATURITY_OPTION, "maturity_option", Eq, Cmp, Bit);
-    filter!(Summary, SUMMARY, "summary", Like);
-    filter!(Description, DESCRIPTION, "description", Like);
-    filter!(Homepage, HOMEPAGE, "homepage_url", Eq, NotEq, Like, In);
-    filter!(Modfile, MODFILE, "modfile", Eq, NotEq, In, Cmp);
-    filter!(MetadataBlob, METADATA_BLOB, "metadata_blob", Eq, NotEq, Like);
-    filter!(MetadataKVP, METADATA_KVP, "metadata_kvp", Eq, NotEq, Like);
-    filter!(Tags, TAGS, "tags", Eq, NotEq, Like);
-
-    filter!(Downloads, DOWNLOADS, "downloads", OrderBy);
-    filter!(Popular, POPULAR, "popular", OrderBy);
-    filter!(Ratings, RATINGS, "ratings", OrderBy);
-    filter!(Subscribers, SUBSCRIBERS, "subscribers", OrderBy);
+    type Endpoint = crate::filter::endpoint::Mods;
+
+    filter!(GameId, GAME_ID, "game_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(Visible, VISIBLE, "visible", crate::types::mods::Visibility: Eq);
+    filter!(MaturityOption, MATURITY_OPTION, "maturity_option", crate::types::mods::MaturityOption: Eq, Cmp, Bit);
+    filter!(Summary, SUMMARY, "summary", String: Like);
+    filter!(Description, DESCRIPTION, "description", String: Like);
+    filter!(Homepage, HOMEPAGE, "homepage_url", String: Eq, NotEq, Like, In);
+    filter!(Modfile, MODFILE, "modfile", u32: Eq, NotEq, In, Cmp);
+    filter!(MetadataBlob, METADATA_BLOB, "metadata_blob", String: Eq, NotEq, Like);
+    filter!(MetadataKVP, METADATA_KVP, "metadata_kvp", String: Eq, NotEq, Like);
+    filter!(Tags, TAGS, "tags", String: Eq, NotEq, Like);
+
+    filter!(Downloads, DOWNLOADS, "downloads", u32: OrderBy);
+    filter!(Popular, POPULAR, "popular", u32: OrderBy);
+    filter!(Ratings, RATINGS, "ratings", u32: OrderBy);
+    filter!(Subscribers, SUBSCRIBERS, "subscribers", u32: OrderBy);
+
+    impl MetadataKVP {
+        /// Filter by the values of a metadata key. The pairs are encoded as `key:value` like
+        /// the metadata of [`Metadata::add`](crate::metadata::Metadata::add).
+        ///
+        /// ```
+        /// use modio::mods::filters::MetadataKVP;
+        ///
+        /// let filter = MetadataKVP::key("difficulty").eq("hard")?;
+        /// let filter = MetadataKVP::key("difficulty").like("h*")?;
+        /// let filter = MetadataKVP::key("difficulty")._in(vec!["easy", "hard"])?;
+        ///
+        /// assert!(MetadataKVP::key("difficulty")._in(vec!["easy,hard"]).is_err());
+        /// # Ok::<_, modio::Error>(())
+        /// ```
+        pub fn key<S: Into<String>>(key: S) -> MetadataKey {
+            MetadataKey { key: key.into() }
+        }
+    }
+
+    /// A metadata key created with [`MetadataKVP::key`].
+    ///
+    /// mod.io has no escape sequences for the `key:value` pairs. The key can't contain `:`, `,`
+    /// or `*`, and the values can't contain `,`. `*` is the wildcard of `like` and `not_like` and
+    /// is rejected by the other operators. Invalid pairs return a validation error.
+    pub struct MetadataKey {
+        key: String,
+    }
+
+    impl MetadataKey {
+        fn filter<V: IntoValues<String>>(&self, op: Operator, value: V) -> Result<Filter<Endpoint>> {
+            let reserved = match op {
+                Operator::Like | Operator::NotLike => ",",
+                _ => ",*",
+            };
+            let values = value.into_values();
+            let mut v = Validator::new();
+            v.reserved(METADATA_KVP, &self.key, ":,*");
+            match values {
+                OneOrMany::One(ref value) => v.reserved(METADATA_KVP, value, reserved),
+                OneOrMany::Many(ref values) => values.iter().for_each(|value| v.reserved(METADATA_KVP, value, reserved)),
+            }
+            v.finish()?;
+
+            let pairs = match values {
+                OneOrMany::One(v) => OneOrMany::One(encode_kvp(&self.key, &v)),
+                OneOrMany::Many(v) => OneOrMany::Many(v.iter().map(|v| encode_kvp(&self.key, v)).collect()),
+            };
+            Ok(Filter::new(METADATA_KVP, op, pairs))
+        }
+
+        pub fn eq<V: IntoValues<String>>(&self, value: V) -> Result<Filter<Endpoint>> {
+            self.filter(Operator::Equals, value)
+        }
+
+        pub fn ne<V: IntoValues<String>>(&self, value: V) -> Result<Filter<Endpoint>> {
+            self.filter(Operator::Not, value)
+        }
+
+        pub fn like<V: IntoValues<String>>(&self, value: V) -> Result<Filter<Endpoint>> {
+            self.filter(Operator::Like, value)
+        }
+
+        pub fn not_like<V: IntoValues<String>>(&self, value: V) -> Result<Filter<Endpoint>> {
+            self.filter(Operator::NotLike, value)
+        }
+
+        pub fn _in<V: IntoValues<String>>(&self, value: V) -> Result<Filter<Endpoint>> {
+            self.filter(Operator::In, value)
+        }
+
+        pub fn not_in<V: IntoValues<String>>(&self, value: V) -> Result<Filter<Endpoint>> {
+            self.filter(Operator::NotIn, value)
+        }
+    }
+
+    /// The mod fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+    pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+        Fulltext::FIELD,
+        Id::FIELD,
+        Name::FIELD,
+        NameId::FIELD,
+        Status::FIELD,
+        DateAdded::FIELD,
+        DateUpdated::FIELD,
+        DateLive::FIELD,
+        SubmittedBy::FIELD,
+        GameId::FIELD,
+        Visible::FIELD,
+        MaturityOption::FIELD,
+        Summary::FIELD,
+        Description::FIELD,
+        Homepage::FIELD,
+        Modfile::FIELD,
+        MetadataBlob::FIELD,
+        MetadataKVP::FIELD,
+        Tags::FIELD,
+        Downloads::FIELD,
+        Popular::FIELD,
+        Ratings::FIELD,
+        Subscribers::FIELD,
+    ]);
 
     /// Mod event filters and sorting.
     ///
@@ -400,8 +586,19 @@ pub mod filters {
         #[doc(inline)]
         pub use crate::filter::prelude::DateAdded;
 
-        filter!(UserId, USER_ID, "user_id", Eq, NotEq, In, Cmp, OrderBy);
-        filter!(EventType, EVENT_TYPE, "event_type", Eq, NotEq, In, OrderBy);
+        type Endpoint = crate::filter::endpoint::ModEvents;
+
+        filter!(UserId, USER_ID, "user_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(EventType, EVENT_TYPE, "event_type", crate::types::mods::EventType: Eq, NotEq, In, OrderBy);
+
+        /// The mod event fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+        pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+            Id::FIELD,
+            ModId::FIELD,
+            UserId::FIELD,
+            DateAdded::FIELD,
+            EventType::FIELD,
+        ]);
     }
 
     /// Mod statistics filters & sorting
@@ -434,11 +631,23 @@ pub mod filters {
         #[doc(inline)]
         pub use crate::filter::prelude::ModId;
 
-        filter!(Popularity, POPULARITY, "popularity_rank_position", Eq, NotEq, In, Cmp, OrderBy);
-        filter!(Downloads, DOWNLOADS, "downloads_total", Eq, NotEq, In, Cmp, OrderBy);
-        filter!(Subscribers, SUBSCRIBERS, "subscribers_total", Eq, NotEq, In, Cmp, OrderBy);
-        filter!(RatingsPositive, RATINGS_POSITIVE, "ratings_positive", Eq, NotEq, In, Cmp, OrderBy);
-        filter!(RatingsNegative, RATINGS_NEGATIVE, "ratings_negative", Eq, NotEq, In, Cmp, OrderBy);
+        type Endpoint = crate::filter::endpoint::ModStats;
+
+        filter!(Popularity, POPULARITY, "popularity_rank_position", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(Downloads, DOWNLOADS, "downloads_total", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(Subscribers, SUBSCRIBERS, "subscribers_total", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(RatingsPositive, RATINGS_POSITIVE, "ratings_positive", u32: Eq, NotEq, In, Cmp, OrderBy);
+        filter!(RatingsNegative, RATINGS_NEGATIVE, "ratings_negative", u32: Eq, NotEq, In, Cmp, OrderBy);
+
+        /// The mod statistics fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+        pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+            ModId::FIELD,
+            Popularity::FIELD,
+            Downloads::FIELD,
+            Subscribers::FIELD,
+            RatingsPositive::FIELD,
+            RatingsNegative::FIELD,
+        ]);
     }
 }
 
@@ -486,11 +695,7 @@ impl AddModOptions {
             .and_then(OsStr::to_str)
             .map_or_else(String::new, ToString::to_string);
 
-        let logo = FileSource {
-            inner: FileStream::open(logo),
-            filename,
-            mime: IMAGE_STAR,
-        };
+        let logo = FileSource::open(logo, filename, IMAGE_STAR);
 
         AddModOptions {
             name: name.into(),
@@ -531,6 +736,37 @@ impl AddModOptions {
             ..self
         }
     }
+
+    /// Check the options before sending them.
+    ///
+    /// Checks the required fields, the length of the text fields and the format, size and
+    /// dimensions of the logo. The tags are not checked, see
+    /// [`validate_with_tags`](#method.validate_with_tags).
+    pub fn validate(&self) -> Result<()> {
+        self.validator().finish()
+    }
+
+    /// Check the options and that the tags are defined by the tag options of the game.
+    pub fn validate_with_tags(&self, options: &[TagOption]) -> Result<()> {
+        let mut v = self.validator();
+        if let Some(ref tags) = self.tags {
+            v.tags("tags", tags, options);
+        }
+        v.finish()
+    }
+
+    fn validator(&self) -> Validator {
+        let mut v = Validator::new();
+        v.required("name", &self.name);
+        v.max_len("name", Some(&self.name), 50);
+        v.max_len("name_id", self.name_id.as_deref(), 80);
+        v.required("summary", &self.summary);
+        v.max_len("summary", Some(&self.summary), 250);
+        v.max_len("description", self.description.as_deref(), 50000);
+        v.max_len("metadata_blob", self.metadata_blob.as_deref(), 50000);
+        v.image("logo", &self.logo, &validation::MOD_LOGO);
+        v
+    }
 }
 
 #[doc(hidden)]
@@ -675,6 +911,7 @@ pub struct AddMediaOptions {
     images: Option<Vec<FileSource>>,
     youtube: Option<Vec<String>>,
     sketchfab: Option<Vec<String>>,
+    progress: Option<Arc<OnProgress>>,
 }
 
 impl AddMediaOptions {
@@ -686,22 +923,18 @@ impl AddMediaOptions {
             .map_or_else(String::new, ToString::to_string);
 
         Self {
-            logo: Some(FileSource {
-                inner: FileStream::open(logo),
-                filename,
-                mime: IMAGE_STAR,
-            }),
+            logo: Some(FileSource::open(logo, filename, IMAGE_STAR)),
             ..self
         }
     }
 
     pub fn images_zip<P: AsRef<Path>>(self, images: P) -> Self {
         Self {
-            images_zip: Some(FileSource {
-                inner: FileStream::open(images),
-                filename: "images.zip".into(),
-                mime: APPLICATION_OCTET_STREAM,
-            }),
+            images_zip: Some(FileSource::open(
+                images,
+                "images.zip".into(),
+                APPLICATION_OCTET_STREAM,
+            )),
             ..self
         }
     }
@@ -718,11 +951,7 @@ impl AddMediaOptions {
                             .and_then(OsStr::to_str)
                             .map_or_else(String::new, ToString::to_string);
 
-                        FileSource {
-                            inner: FileStream::open(file),
-                            filename,
-                            mime: IMAGE_STAR,
-                        }
+                        FileSource::open(file, filename, IMAGE_STAR)
                     })
                     .collect::<Vec<_>>(),
             ),
@@ -743,21 +972,50 @@ impl AddMediaOptions {
             ..self
         }
     }
+
+    /// Report the upload progress of the logo and image files to the callback.
+    pub fn progress<F>(self, f: F) -> Self
+    where
+        F: Fn(Progress<'_>) + Send + Sync + 'static,
+    {
+        Self {
+            progress: Some(Arc::new(f)),
+            ..self
+        }
+    }
+
+    /// Check the format, size and dimensions of the images before uploading them.
+    pub fn validate(&self) -> Result<()> {
+        let mut v = Validator::new();
+        if let Some(ref logo) = self.logo {
+            v.image("logo", logo, &validation::MOD_LOGO);
+        }
+        if let Some(ref zip) = self.images_zip {
+            v.archive("images", zip, validation::MAX_IMAGES_ZIP_SIZE);
+        }
+        if let Some(ref images) = self.images {
+            for (i, image) in images.iter().enumerate() {
+                v.image(&format!("image{}", i), image, &validation::MOD_IMAGE);
+            }
+        }
+        v.finish()
+    }
 }
 
 #[doc(hidden)]
 impl From<AddMediaOptions> for Form {
     fn from(opts: AddMediaOptions) -> Form {
         let mut form = Form::new();
+        let progress = opts.progress.as_ref();
         if let Some(logo) = opts.logo {
-            form = form.part("logo", logo.into());
+            form = form.part("logo", logo.with_progress(progress).into());
         }
         if let Some(zip) = opts.images_zip {
-            form = form.part("images", zip.into());
+            form = form.part("images", zip.with_progress(progress).into());
         }
         if let Some(images) = opts.images {
             for (i, image) in images.into_iter().enumerate() {
-                form = form.part(format!("image{}", i), image.into());
+                form = form.part(format!("image{}", i), image.with_progress(progress).into());
             }
         }
         if let Some(youtube) = opts.youtube {
@@ -819,3 +1077,43 @@ impl QueryString for DeleteMediaOptions {
         ser.finish()
     }
 }
+
+/// Options of [`Mods::search`](struct.Mods.html#method.search).
+pub struct SearchOptions {
+    pub(crate) query: String,
+    pub(crate) tags: Option<Vec<String>>,
+    pub(crate) candidates: Option<usize>,
+    pub(crate) limit: Option<usize>,
+    pub(crate) offset: Option<usize>,
+}
+
+impl SearchOptions {
+    pub fn new<S: Into<String>>(query: S) -> Self {
+        Self {
+            query: query.into(),
+            tags: None,
+            candidates: None,
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// Only search mods with all of these tags.
+    pub fn tags(self, tags: &[String]) -> Self {
+        Self {
+            tags: Some(tags.to_vec()),
+            ..self
+        }
+    }
+
+    option!(
+        /// The maximum number of mods requested for each alternative of the search, e.g. the
+        /// fulltext search or a name match. Defaults to `100`.
+        candidates: usize
+    );
+    option!(
+        /// The number of ranked mods returned. Defaults to `20`.
+        limit: usize
+    );
+    option!(offset: usize);
+}
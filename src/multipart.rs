@@ -1,25 +1,77 @@
 use std::io::Error;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use bytes::{Bytes, BytesMut};
-use futures::{task, Async, Future, Poll, Stream};
+use futures::{future, task, Async, Future, Poll, Stream};
 use mime::Mime;
 use reqwest::r#async::multipart::Part;
 use tokio_codec::{BytesCodec, FramedRead};
 use tokio_fs::file::{File, OpenFuture};
 use tokio_io::AsyncRead;
 
+use crate::validation;
+
 pub struct FileSource {
     pub inner: FileStream,
     pub filename: String,
     pub mime: Mime,
 }
 
+/// Upload progress of a file.
+#[derive(Debug)]
+pub struct Progress<'a> {
+    pub filename: &'a str,
+    /// The number of bytes passed to the connection so far.
+    pub sent: u64,
+    /// The size of the file if known from its metadata.
+    pub total: Option<u64>,
+}
+
+/// Callback type for upload progress.
+pub type OnProgress = dyn Fn(Progress<'_>) + Send + Sync;
+
+struct ProgressState {
+    callback: Arc<OnProgress>,
+    filename: String,
+    sent: u64,
+}
+
+impl FileSource {
+    /// Open a file and detect its content type from the magic bytes. `default` is used if the
+    /// type is unknown.
+    pub fn open<P: AsRef<Path>>(path: P, filename: String, default: Mime) -> FileSource {
+        let path = path.as_ref();
+        FileSource {
+            inner: FileStream::open(path),
+            filename,
+            mime: validation::content_type(path).unwrap_or(default),
+        }
+    }
+
+    /// Report the progress of reading the file to the callback.
+    pub fn with_progress(mut self, callback: Option<&Arc<OnProgress>>) -> FileSource {
+        if let Some(callback) = callback {
+            self.inner.progress = Some(ProgressState {
+                callback: callback.clone(),
+                filename: self.filename.clone(),
+                sent: 0,
+            });
+        }
+        self
+    }
+
+    /// Return the path of the file if the source was opened from a path.
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.path.as_deref()
+    }
+}
+
 impl From<FileSource> for Part {
     fn from(source: FileSource) -> Part {
         Part::stream(source.inner)
             .file_name(source.filename)
-            .mime_str(&source.mime.to_string())
+            .mime_str(source.mime.as_ref())
             .expect("FileSource::into::<Part>()")
     }
 }
@@ -27,10 +79,67 @@ impl From<FileSource> for Part {
 enum State {
     File(OpenFuture<PathBuf>),
     Read(FramedRead<Box<dyn AsyncRead + Send + Sync>, BytesCodec>),
+    Stream(Box<dyn Stream<Item = Bytes, Error = Error> + Send + Sync>),
 }
 
 pub struct FileStream {
     state: Option<State>,
+    checksum: Option<Checksum>,
+    progress: Option<ProgressState>,
+    total: Option<u64>,
+    path: Option<PathBuf>,
+}
+
+/// MD5 hash and size of the data read by a `FileStream`, shared with the owner of the stream.
+#[derive(Clone, Default)]
+pub struct Checksum {
+    inner: Arc<Mutex<ChecksumState>>,
+}
+
+#[derive(Default)]
+struct ChecksumState {
+    ctx: Option<md5::Context>,
+    size: u64,
+    md5: Option<String>,
+}
+
+impl Checksum {
+    fn update(&self, data: &[u8]) {
+        let mut state = self.inner.lock().expect("checksum state");
+        state
+            .ctx
+            .get_or_insert_with(md5::Context::new)
+            .consume(data);
+        state.size += data.len() as u64;
+    }
+
+    fn finish(&self) {
+        let mut state = self.inner.lock().expect("checksum state");
+        if state.md5.is_none() {
+            let ctx = state.ctx.take().unwrap_or_else(md5::Context::new);
+            state.md5 = Some(format!("{:x}", ctx.compute()));
+        }
+    }
+
+    /// Return the md5 hash once the stream is read to the end.
+    pub fn md5(&self) -> Option<String> {
+        self.inner.lock().expect("checksum state").md5.clone()
+    }
+
+    /// Return the number of bytes read so far.
+    pub fn size(&self) -> u64 {
+        self.inner.lock().expect("checksum state").size
+    }
+
+    /// Create a part containing the md5 hash. The hash is read when the part is sent, so the
+    /// part must be added to the form after the part of the hashed stream.
+    pub fn into_part(self) -> Part {
+        let lazy = future::lazy(move || {
+            self.md5()
+                .ok_or_else(|| Error::other("file stream was not read to the end"))
+        });
+        Part::stream(lazy.into_stream())
+    }
 }
 
 impl FileStream {
@@ -41,14 +150,64 @@ impl FileStream {
         );
         FileStream {
             state: Some(State::Read(framed)),
+            checksum: None,
+            progress: None,
+            total: None,
+            path: None,
         }
     }
 
     pub fn open<P: AsRef<Path>>(file: P) -> FileStream {
+        let total = std::fs::metadata(file.as_ref()).ok().map(|m| m.len());
+        let path = file.as_ref().to_path_buf();
         FileStream {
-            state: Some(State::File(File::open(file.as_ref().to_path_buf()))),
+            state: Some(State::File(File::open(path.clone()))),
+            checksum: None,
+            progress: None,
+            total,
+            path: Some(path),
         }
     }
+
+    pub fn from_stream<S>(inner: S) -> FileStream
+    where
+        S: 'static + Stream<Item = Bytes, Error = Error> + Send + Sync,
+    {
+        FileStream {
+            state: Some(State::Stream(Box::new(inner))),
+            checksum: None,
+            progress: None,
+            total: None,
+            path: None,
+        }
+    }
+
+    /// Compute the md5 hash and size of the data while the stream is read.
+    pub fn checksum(&mut self) -> Checksum {
+        self.checksum.get_or_insert_with(Checksum::default).clone()
+    }
+}
+
+impl FileStream {
+    fn chunk(&mut self, bytes: Option<Bytes>) -> Option<Bytes> {
+        if let Some(ref checksum) = self.checksum {
+            match bytes {
+                Some(ref bytes) => checksum.update(bytes),
+                None => checksum.finish(),
+            }
+        }
+        if let Some(ref mut progress) = self.progress {
+            if let Some(ref bytes) = bytes {
+                progress.sent += bytes.len() as u64;
+                (progress.callback)(Progress {
+                    filename: &progress.filename,
+                    sent: progress.sent,
+                    total: self.total,
+                });
+            }
+        }
+        bytes
+    }
 }
 
 impl Stream for FileStream {
@@ -73,10 +232,19 @@ impl Stream for FileStream {
             Some(State::Read(mut stream)) => {
                 let ret = stream.poll();
                 self.state = Some(State::Read(stream));
-                if let Async::Ready(bytes) = ret? {
-                    Ok(Async::Ready(bytes.map(BytesMut::freeze)))
-                } else {
-                    Ok(Async::NotReady)
+                match ret? {
+                    Async::Ready(bytes) => {
+                        Ok(Async::Ready(self.chunk(bytes.map(BytesMut::freeze))))
+                    }
+                    Async::NotReady => Ok(Async::NotReady),
+                }
+            }
+            Some(State::Stream(mut stream)) => {
+                let ret = stream.poll();
+                self.state = Some(State::Stream(stream));
+                match ret? {
+                    Async::Ready(bytes) => Ok(Async::Ready(self.chunk(bytes))),
+                    Async::NotReady => Ok(Async::NotReady),
                 }
             }
             None => unreachable!(),
@@ -105,6 +273,43 @@ mod tests {
         rt.block_on(fs).unwrap();
     }
 
+    #[test]
+    fn checksum() {
+        let mut rt = Runtime::new().expect("new rt");
+
+        let mut fs = FileStream::new(io::Cursor::new(b"Hello World"));
+        let checksum = fs.checksum();
+        assert_eq!(checksum.md5(), None);
+
+        rt.block_on(fs.concat2()).unwrap();
+        assert_eq!(checksum.md5().unwrap(), "b10a8db164e0754105b7a99be72e3fe5");
+        assert_eq!(checksum.size(), 11);
+    }
+
+    #[test]
+    fn progress() {
+        let mut rt = Runtime::new().expect("new rt");
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let callback: Arc<OnProgress> = {
+            let reported = reported.clone();
+            Arc::new(move |p: Progress<'_>| {
+                assert_eq!(p.filename, "Cargo.toml");
+                reported.lock().unwrap().push((p.sent, p.total));
+            })
+        };
+        let source = FileSource {
+            inner: FileStream::open("Cargo.toml"),
+            filename: "Cargo.toml".into(),
+            mime: mime::APPLICATION_OCTET_STREAM,
+        }
+        .with_progress(Some(&callback));
+
+        rt.block_on(source.inner.concat2()).unwrap();
+        let len = include_bytes!("../Cargo.toml").len() as u64;
+        assert_eq!(reported.lock().unwrap().last(), Some(&(len, Some(len))));
+    }
+
     #[test]
     fn open() {
         let mut rt = Runtime::new().expect("new rt");
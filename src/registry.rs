@@ -0,0 +1,352 @@
+//! Registry of installed mods
+//!
+//! The registry records the installed file of each mod and is persisted as JSON file. It can be
+//! checked against mod.io to find out which mods are outdated or were removed.
+//!
+//! # Example
+//! ```no_run
+//! use modio::registry::{InstalledMod, Registry, Update};
+//! use modio::{Credentials, Error, Modio};
+//! use tokio::runtime::Runtime;
+//!
+//! fn main() -> Result<(), Error> {
+//!     let mut rt = Runtime::new().expect("new rt");
+//!     let modio = Modio::new(
+//!         Credentials::ApiKey(String::from("user-or-game-api-key")),
+//!     )?;
+//!
+//!     let mut registry = Registry::load("mods.json")?;
+//!     let file = rt.block_on(modio.mod_(5, 19).file(27).get())?;
+//!     registry.insert(InstalledMod::from_file(5, &file, "mods/19"));
+//!     registry.save("mods.json")?;
+//!
+//!     for update in rt.block_on(registry.check_updates(&modio))? {
+//!         match update {
+//!             Update::Available { installed, .. } => println!("outdated: {}", installed.mod_id),
+//!             Update::Removed(installed) => println!("removed: {}", installed.mod_id),
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use futures::{future, Future as StdFuture, Stream as StdStream};
+
+use crate::error::{self, Result};
+use crate::files::File;
+use crate::filter::prelude::*;
+use crate::me::filters::events::EventType as EventTypeFilter;
+use crate::mods::Mod;
+use crate::types::{Event, EventType};
+use crate::{Future, Modio};
+
+/// The installed file of a mod.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstalledMod {
+    pub game_id: u32,
+    pub mod_id: u32,
+    pub file_id: u32,
+    pub version: Option<String>,
+    pub md5: String,
+    pub path: PathBuf,
+}
+
+impl InstalledMod {
+    /// Create an entry for a modfile installed at `path`.
+    pub fn from_file<P: AsRef<Path>>(game_id: u32, file: &File, path: P) -> InstalledMod {
+        InstalledMod {
+            game_id,
+            mod_id: file.mod_id,
+            file_id: file.id,
+            version: file.version.clone(),
+            md5: file.filehash.md5.clone(),
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+}
+
+/// Result of checking an installed mod against mod.io.
+#[derive(Debug)]
+pub enum Update {
+    /// The primary file of the mod changed.
+    ///
+    /// `file` is the new primary file or `None` if the change was reported by an event.
+    Available {
+        installed: InstalledMod,
+        file: Option<Box<File>>,
+    },
+    /// The mod was deleted or is no longer available.
+    Removed(InstalledMod),
+}
+
+/// Persistent registry of installed mods.
+#[derive(Debug, Default)]
+pub struct Registry {
+    mods: BTreeMap<(u32, u32), InstalledMod>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegistryFile {
+    mods: Vec<InstalledMod>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Default::default()
+    }
+
+    /// Load the registry from a JSON file. A missing file results in an empty registry.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Registry> {
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Registry::new()),
+            Err(e) => return Err(error::from(e)),
+        };
+        let file: RegistryFile = serde_json::from_slice(&data).map_err(error::from)?;
+        Ok(file.mods.into_iter().collect())
+    }
+
+    /// Save the registry as JSON file.
+    ///
+    /// The data is written to a temporary file next to `path` first and then renamed, so an
+    /// existing registry is not corrupted by a failed write.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let file = RegistryFile {
+            mods: self.mods.values().cloned().collect(),
+        };
+        let data = serde_json::to_vec_pretty(&file).map_err(error::from)?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data).map_err(error::from)?;
+        fs::rename(&tmp, path).map_err(error::from)
+    }
+
+    /// Add or replace the entry for a mod and return the previous entry.
+    pub fn insert(&mut self, installed: InstalledMod) -> Option<InstalledMod> {
+        self.mods
+            .insert((installed.game_id, installed.mod_id), installed)
+    }
+
+    /// Remove the entry for a mod.
+    pub fn remove(&mut self, game_id: u32, mod_id: u32) -> Option<InstalledMod> {
+        self.mods.remove(&(game_id, mod_id))
+    }
+
+    pub fn get(&self, game_id: u32, mod_id: u32) -> Option<&InstalledMod> {
+        self.mods.get(&(game_id, mod_id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &InstalledMod> {
+        self.mods.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.mods.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mods.is_empty()
+    }
+
+    /// Compare the installed files with the primary files of the mods.
+    ///
+    /// Mods that are no longer returned by mod.io are reported as removed.
+    pub fn check_updates(&self, modio: &Modio) -> Future<Vec<Update>> {
+        let mut games = BTreeMap::new();
+        for (game_id, mod_id) in self.mods.keys() {
+            games.entry(*game_id).or_insert_with(Vec::new).push(*mod_id);
+        }
+
+        let requests = games
+            .into_iter()
+            .map(|(game_id, ids)| modio.game(game_id).mods().iter(&Id::_in(ids)).collect())
+            .collect::<Vec<_>>();
+
+        let installed = self.mods.clone();
+        Box::new(
+            future::join_all(requests)
+                .map(move |mods| compare(installed, mods.into_iter().flatten())),
+        )
+    }
+
+    /// Check the events of the authenticated user added after `since` for changed or removed
+    /// mods. [required: token]
+    pub fn check_events(&self, modio: &Modio, since: u64) -> Future<Vec<Update>> {
+        let filter = DateAdded::gt(since)
+            .and(EventTypeFilter::_in(vec![
+                EventType::ModfileChanged,
+                EventType::ModAvailable,
+                EventType::ModUnavailable,
+                EventType::ModDeleted,
+            ]))
+            .order_by(Id::asc());
+
+        let installed = self.mods.clone();
+        Box::new(
+            modio
+                .me()
+                .events(&filter)
+                .collect()
+                .map(move |events| apply_events(&installed, events)),
+        )
+    }
+}
+
+impl std::iter::FromIterator<InstalledMod> for Registry {
+    fn from_iter<I: IntoIterator<Item = InstalledMod>>(iter: I) -> Registry {
+        let mut registry = Registry::new();
+        for installed in iter {
+            registry.insert(installed);
+        }
+        registry
+    }
+}
+
+fn compare<I>(mut installed: BTreeMap<(u32, u32), InstalledMod>, mods: I) -> Vec<Update>
+where
+    I: Iterator<Item = Mod>,
+{
+    let mut updates = Vec::new();
+    for m in mods {
+        let entry = match installed.remove(&(m.game_id, m.id)) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        match m.modfile {
+            Some(file) if file.id != entry.file_id => updates.push(Update::Available {
+                installed: entry,
+                file: Some(Box::new(file)),
+            }),
+            Some(_) => {}
+            None => updates.push(Update::Removed(entry)),
+        }
+    }
+    updates.extend(installed.into_values().map(Update::Removed));
+    updates
+}
+
+fn apply_events(installed: &BTreeMap<(u32, u32), InstalledMod>, events: Vec<Event>) -> Vec<Update> {
+    // Whether the modfile changed and whether the mod is currently removed. A changed modfile
+    // stays pending while the mod is unavailable.
+    let mut states: BTreeMap<(u32, u32), (bool, bool)> = BTreeMap::new();
+    for event in events {
+        let key = (event.game_id, event.mod_id);
+        if !installed.contains_key(&key) {
+            continue;
+        }
+        let (changed, removed) = states.entry(key).or_default();
+        match event.event_type {
+            EventType::ModfileChanged => *changed = true,
+            EventType::ModUnavailable | EventType::ModDeleted => *removed = true,
+            EventType::ModAvailable => *removed = false,
+            _ => {}
+        }
+    }
+    states
+        .into_iter()
+        .filter_map(|(key, state)| {
+            let entry = installed[&key].clone();
+            match state {
+                (_, true) => Some(Update::Removed(entry)),
+                (true, false) => Some(Update::Available {
+                    installed: entry,
+                    file: None,
+                }),
+                (false, false) => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(game_id: u32, mod_id: u32) -> InstalledMod {
+        InstalledMod {
+            game_id,
+            mod_id,
+            file_id: 1,
+            version: Some("1.0".to_string()),
+            md5: "d41d8cd98f00b204e9800998ecf8427e".to_string(),
+            path: PathBuf::from(format!("mods/{}", mod_id)),
+        }
+    }
+
+    fn event(id: u32, mod_id: u32, event_type: &str) -> Event {
+        serde_json::from_str(&format!(
+            r#"{{"id":{},"game_id":1,"mod_id":{},"user_id":1,"date_added":0,"event_type":"{}"}}"#,
+            id, mod_id, event_type
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = std::env::temp_dir().join(format!("modio-registry-{}.json", std::process::id()));
+        let mut registry = Registry::new();
+        registry.insert(installed(1, 2));
+        registry.insert(installed(1, 3));
+        registry.save(&path).unwrap();
+
+        let loaded = Registry::load(&path).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get(1, 3), Some(&installed(1, 3)));
+        fs::remove_file(&path).unwrap();
+
+        assert!(Registry::load(&path).unwrap().is_empty());
+    }
+
+    #[test]
+    fn events() {
+        let registry: Registry = vec![installed(1, 2), installed(1, 3), installed(1, 4)]
+            .into_iter()
+            .collect();
+        let events = vec![
+            event(1, 2, "MODFILE_CHANGED"),
+            event(2, 3, "MOD_UNAVAILABLE"),
+            event(3, 4, "MOD_DELETED"),
+            event(4, 3, "MOD_AVAILABLE"),
+            event(5, 99, "MODFILE_CHANGED"),
+        ];
+        let updates = apply_events(&registry.mods, events);
+        assert_eq!(updates.len(), 2);
+        match &updates[0] {
+            Update::Available { installed, file } => {
+                assert_eq!(installed.mod_id, 2);
+                assert!(file.is_none());
+            }
+            u => panic!("unexpected update: {:?}", u),
+        }
+        match &updates[1] {
+            Update::Removed(installed) => assert_eq!(installed.mod_id, 4),
+            u => panic!("unexpected update: {:?}", u),
+        }
+    }
+
+    #[test]
+    fn changed_while_unavailable() {
+        let registry: Registry = vec![installed(1, 2), installed(1, 3)].into_iter().collect();
+        let events = vec![
+            event(1, 2, "MODFILE_CHANGED"),
+            event(2, 2, "MOD_UNAVAILABLE"),
+            event(3, 2, "MOD_AVAILABLE"),
+            event(4, 3, "MOD_UNAVAILABLE"),
+            event(5, 3, "MODFILE_CHANGED"),
+        ];
+        let updates = apply_events(&registry.mods, events);
+        assert_eq!(updates.len(), 2);
+        match &updates[0] {
+            Update::Available { installed, .. } => assert_eq!(installed.mod_id, 2),
+            u => panic!("unexpected update: {:?}", u),
+        }
+        match &updates[1] {
+            Update::Removed(installed) => assert_eq!(installed.mod_id, 3),
+            u => panic!("unexpected update: {:?}", u),
+        }
+    }
+}
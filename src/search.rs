@@ -0,0 +1,323 @@
+//! Candidate filters and local relevance ranking for `Mods::search`.
+//!
+//! The candidates are requested from the fulltext search, parts of the query words matched
+//! against the mod names and the query words as tags. Name parts instead of whole words find
+//! mods even if a word of the query contains a typo. Every alternative is requested separately
+//! and capped at its own most popular candidates, so the many name part matches can't push out
+//! the results of the fulltext search.
+//!
+//! The ranking adds up the text relevance of the query words, a bonus for names starting with
+//! the query and small amounts for the popularity and the ratings of the mods. Results of the
+//! fulltext search get a bonus for matching the description or other fields mod.io searches.
+//! Mods without any relevance are ranked after all others.
+use std::collections::HashSet;
+
+use crate::filter::endpoint::Mods;
+use crate::filter::prelude::*;
+use crate::mods::filters::{Popular, Tags};
+use crate::mods::{Mod, SearchOptions};
+use crate::List;
+
+pub(crate) const DEFAULT_CANDIDATES: usize = 100;
+const DEFAULT_LIMIT: usize = 20;
+const PAGE_SIZE: usize = 100;
+const FULLTEXT_SCORE: f64 = 0.5;
+
+/// Split the query into words.
+fn words(query: &str) -> Vec<String> {
+    words_of(query).into_iter().map(ToOwned::to_owned).collect()
+}
+
+/// The name parts of a word. A single typo leaves at least one half of a longer word intact.
+fn fragments(word: &str) -> Vec<String> {
+    let chars = word.chars().collect::<Vec<_>>();
+    if chars.len() < 4 {
+        return vec![word.to_owned()];
+    }
+    let (a, b) = chars.split_at(chars.len() / 2);
+    vec![a.iter().collect(), b.iter().collect()]
+}
+
+/// The alternatives of the search, the fulltext search first.
+pub(crate) fn filters(options: &SearchOptions) -> Vec<Filter<Mods>> {
+    let tags = options.tags.clone().unwrap_or_default();
+    let with_tags = |filter: Filter<Mods>| {
+        if tags.is_empty() {
+            filter
+        } else {
+            filter.and(Tags::eq(tags.clone()))
+        }
+    };
+
+    let words = words(&options.query);
+    let mut filters = Vec::new();
+    if words.is_empty() {
+        filters.push(with_tags(Filter::default().cast()));
+    } else {
+        filters.push(with_tags(Fulltext::eq(options.query.as_str()).cast()));
+
+        let mut names = words.iter().flat_map(|w| fragments(w)).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        for name in names {
+            filters.push(with_tags(Name::like(format!("*{}*", name)).cast()));
+        }
+        for word in &words {
+            let mut tags = tags.clone();
+            tags.push(word.clone());
+            filters.push(Tags::eq(tags));
+        }
+    }
+    let candidates = options.candidates.unwrap_or(DEFAULT_CANDIDATES);
+    filters
+        .into_iter()
+        .map(|f| f.order_by(Popular::asc()).limit(candidates.min(PAGE_SIZE)))
+        .collect()
+}
+
+/// Rank the candidates of the alternatives returned by `filters`.
+pub(crate) fn rank(options: &SearchOptions, candidates: Vec<Vec<Mod>>) -> List<Mod> {
+    let query = options.query.trim().to_lowercase();
+    let words = words(&query);
+
+    let mut fulltext = HashSet::new();
+    let mut seen = HashSet::new();
+    let mut mods = Vec::new();
+    for (i, list) in candidates.into_iter().enumerate() {
+        for m in list {
+            if i == 0 && !words.is_empty() {
+                fulltext.insert(m.id);
+            }
+            if seen.insert(m.id) {
+                mods.push(m);
+            }
+        }
+    }
+
+    let mut scored = mods
+        .into_iter()
+        .map(|m| {
+            let tags = m.tags.iter().map(|t| t.name.as_str());
+            let mut text = text_score(&query, &words, &m.name, tags, &m.summary);
+            if fulltext.contains(&m.id) {
+                text += FULLTEXT_SCORE;
+            }
+            let score = if text > 0.0 || words.is_empty() {
+                text + popularity(&m)
+            } else {
+                -1.0 / (1.0 + popularity(&m))
+            };
+            (score, m)
+        })
+        .collect::<Vec<_>>();
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let total = scored.len();
+    let offset = options.offset.unwrap_or(0);
+    let limit = options.limit.unwrap_or(DEFAULT_LIMIT);
+    let data = scored
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|(_, m)| m)
+        .collect::<Vec<_>>();
+    List {
+        count: data.len() as u32,
+        total: total as u32,
+        limit: limit as u32,
+        offset: offset as u32,
+        data,
+    }
+}
+
+/// The relevance of the name, tags and summary for the query.
+fn text_score<'a, I>(query: &str, words: &[String], name: &str, tags: I, summary: &str) -> f64
+where
+    I: Iterator<Item = &'a str> + Clone,
+{
+    let name = name.to_lowercase();
+    let summary = summary.to_lowercase();
+    let name_words = words_of(&name);
+
+    let mut score = 0.0;
+    if !query.is_empty() {
+        if name == query {
+            score += 2.0;
+        } else if name.starts_with(query) {
+            score += 1.0;
+        }
+    }
+    for word in words {
+        let in_name = name_words
+            .iter()
+            .map(|n| similarity(word, n))
+            .fold(0.0, f64::max);
+        let in_tags = if tags.clone().any(|t| t.to_lowercase() == *word) {
+            0.8
+        } else {
+            0.0
+        };
+        let in_summary = if summary.contains(word.as_str()) {
+            0.3
+        } else {
+            0.0
+        };
+        score += in_name.max(in_tags).max(in_summary);
+    }
+    score
+}
+
+fn words_of(s: &str) -> Vec<&str> {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// The similarity of a query word and a word of the name between `0` and `1`.
+fn similarity(word: &str, name: &str) -> f64 {
+    if word == name {
+        return 1.0;
+    }
+    if name.starts_with(word) {
+        return 0.9;
+    }
+    let word = word.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let typos = match word.len() {
+        0..=3 => return 0.0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let full = distance(&word, &name);
+    if full <= typos {
+        return 0.8 - 0.15 * full as f64;
+    }
+    // The query may stop in the middle of a word.
+    if name.len() > word.len() {
+        let prefix = distance(&word, &name[..word.len()]);
+        if prefix <= typos {
+            return 0.6 - 0.15 * prefix as f64;
+        }
+    }
+    0.0
+}
+
+/// Optimal string alignment distance, the Levenshtein distance with transpositions of adjacent
+/// characters.
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, d) in rows[0].iter_mut().enumerate() {
+        *d = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut d = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = d;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Popularity from downloads and subscribers plus the weighted ratings, below `1.5`.
+fn popularity(m: &Mod) -> f64 {
+    let stats = &m.stats;
+    let downloads = (1.0 + f64::from(stats.downloads_total)).log10() / 10.0;
+    let subscribers = (1.0 + f64::from(stats.subscribers_total)).log10() / 10.0;
+    downloads + subscribers + f64::from(stats.ratings.weighted_aggregate) * 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QueryString;
+
+    fn score(query: &str, name: &str, tags: &[&str]) -> f64 {
+        let query = query.to_lowercase();
+        text_score(&query, &words(&query), name, tags.iter().cloned(), "")
+    }
+
+    #[test]
+    fn typos() {
+        let d = |a: &str, b: &str| {
+            distance(
+                &a.chars().collect::<Vec<_>>(),
+                &b.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(d("castle", "castle"), 0);
+        assert_eq!(d("catsle", "castle"), 1);
+        assert_eq!(d("castel", "castle"), 1);
+        assert_eq!(d("cstle", "castle"), 1);
+        assert_eq!(d("kitten", "sitting"), 3);
+
+        assert!(similarity("castel", "castle") > 0.0);
+        assert!(similarity("castl", "castles") > 0.0);
+        assert_eq!(similarity("cat", "car"), 0.0);
+        assert_eq!(fragments("castel"), ["cas", "tel"]);
+        assert_eq!(fragments("map"), ["map"]);
+    }
+
+    #[test]
+    fn ranking() {
+        let exact = score("Castle Map", "Castle Map", &[]);
+        let prefix = score("Castle", "Castle Map", &[]);
+        let typo = score("Castel", "Castle Map", &[]);
+        let tag = score("map", "Dungeon", &["Map"]);
+        let none = score("castle", "Space Station", &["Map"]);
+
+        assert!(exact > prefix);
+        assert!(prefix > typo);
+        assert!(typo > 0.0 && tag > 0.0);
+        assert_eq!(none, 0.0);
+    }
+
+    #[test]
+    fn fulltext_results() {
+        use crate::tests::mod_;
+
+        let dungeon = || mod_(1, "Dungeon", (0, 0.0, 0), &[], &[]);
+        let candidates = vec![
+            vec![dungeon()],
+            vec![mod_(2, "Cascade", (1000, 1.0, 1000), &[], &[]), dungeon()],
+            vec![mod_(3, "Castle", (0, 0.0, 0), &[], &[])],
+        ];
+        let list = rank(&SearchOptions::new("castel").limit(2), candidates);
+        let ids = list.data.iter().map(|m| m.id).collect::<Vec<_>>();
+        assert_eq!(ids, [3, 1]);
+        assert_eq!((list.count, list.total, list.limit), (2, 3, 2));
+    }
+
+    #[test]
+    fn candidates() {
+        let options = SearchOptions::new("Castel").tags(&["Map".to_string()]);
+        let queries = filters(&options)
+            .iter()
+            .map(QueryString::to_query_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            queries,
+            [
+                "_q=Castel&tags=Map&_sort=popular&_limit=100",
+                "name-lk=*Cas*&tags=Map&_sort=popular&_limit=100",
+                "name-lk=*tel*&tags=Map&_sort=popular&_limit=100",
+                "tags=Map%2CCastel&_sort=popular&_limit=100",
+            ]
+        );
+
+        let options = SearchOptions::new("").candidates(20);
+        let queries = filters(&options)
+            .iter()
+            .map(QueryString::to_query_string)
+            .collect::<Vec<_>>();
+        assert_eq!(queries, ["_sort=popular&_limit=20"]);
+    }
+}
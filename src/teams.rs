@@ -28,7 +28,7 @@ impl Members {
     /// List all team members.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<TeamMember>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Teams>>) -> Future<List<TeamMember>> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -40,7 +40,7 @@ impl Members {
     /// Provids a stream over all team members.
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<TeamMember> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Teams>>) -> Stream<TeamMember> {
         let mut uri = vec![self.path("")];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -49,6 +49,13 @@ impl Members {
         self.modio.stream(&uri.join("?"))
     }
 
+    /// Provides a stream over all team members that pass any of the alternatives.
+    ///
+    /// See [OR-queries](../filter/any/index.html).
+    pub fn iter_any(&self, filter: &AnyOf<impl Scope<endpoint::Teams>>) -> Stream<TeamMember> {
+        self.modio.stream_any(&self.path(""), filter)
+    }
+
     /// Add a team member by email. [required: token]
     pub fn add(&self, options: &InviteTeamMemberOptions) -> Future<()> {
         token_required!(self.modio);
@@ -116,10 +123,23 @@ pub mod filters {
     #[doc(inline)]
     pub use crate::filter::prelude::DateAdded;
 
-    filter!(UserId, USER_ID, "user_id", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(Username, USERNAME, "username", Eq, NotEq, In, Like, OrderBy);
-    filter!(Level, LEVEL, "level", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(Position, POSITION, "position", Eq, NotEq, In, Like, OrderBy);
+    type Endpoint = crate::filter::endpoint::Teams;
+
+    filter!(UserId, USER_ID, "user_id", u32: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(Username, USERNAME, "username", String: Eq, NotEq, In, Like, OrderBy);
+    filter!(Level, LEVEL, "level", crate::types::mods::TeamLevel: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(Position, POSITION, "position", String: Eq, NotEq, In, Like, OrderBy);
+
+    /// The team member fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+    pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+        Fulltext::FIELD,
+        Id::FIELD,
+        DateAdded::FIELD,
+        UserId::FIELD,
+        Username::FIELD,
+        Level::FIELD,
+        Position::FIELD,
+    ]);
 }
 
 #[derive(Debug)]
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::task::{self, Task};
+use futures::{Async, Future, Poll, Stream};
+use tokio_timer::Delay;
+
+use crate::error::{self, Error};
+
+/// Bandwidth limit shared by all clones of a `Modio` client.
+///
+/// The limit is enforced by delaying the delivery of received chunks. The body of the response
+/// is not polled while a chunk is delayed, so the transfer itself slows down. Changing the rate
+/// wakes the streams with a delayed chunk to reserve it again at the new rate.
+#[derive(Clone, Debug)]
+pub(crate) struct Throttle {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    rate: Option<u64>,
+    next: Instant,
+    /// Incremented by `set_rate`, the deadlines of older reservations are stale.
+    generation: u64,
+    /// The tasks of the streams waiting for a delayed chunk, keyed by stream id.
+    waiting: HashMap<usize, Task>,
+    ids: usize,
+}
+
+impl Throttle {
+    pub fn new(rate: Option<u64>) -> Throttle {
+        Throttle {
+            state: Arc::new(Mutex::new(State {
+                rate: rate.filter(|r| *r > 0),
+                next: Instant::now(),
+                generation: 0,
+                waiting: HashMap::new(),
+                ids: 0,
+            })),
+        }
+    }
+
+    pub fn rate(&self) -> Option<u64> {
+        self.state.lock().expect("throttle state").rate
+    }
+
+    pub fn set_rate(&self, rate: Option<u64>) {
+        let mut state = self.state.lock().expect("throttle state");
+        state.rate = rate.filter(|r| *r > 0);
+        state.next = Instant::now();
+        state.generation += 1;
+        for (_, task) in state.waiting.drain() {
+            task.notify();
+        }
+    }
+
+    /// Reserves bandwidth for `len` bytes and returns the generation of the rate with the
+    /// instant at which they may be delivered, or `None` if no limit is set.
+    fn reserve(&self, len: usize) -> (u64, Option<Instant>) {
+        let mut state = self.state.lock().expect("throttle state");
+        let rate = match state.rate {
+            Some(rate) => rate,
+            None => return (state.generation, None),
+        };
+        let cost = Duration::from_nanos((len as u64).saturating_mul(1_000_000_000) / rate);
+        let start = std::cmp::max(state.next, Instant::now());
+        state.next = start + cost;
+        (state.generation, Some(state.next))
+    }
+
+    /// Registers the current task to be woken by `set_rate`. Returns `false` if the rate
+    /// changed since `generation`.
+    fn park(&self, id: usize, generation: u64) -> bool {
+        let mut state = self.state.lock().expect("throttle state");
+        if state.generation != generation {
+            return false;
+        }
+        state.waiting.insert(id, task::current());
+        true
+    }
+
+    fn unpark(&self, id: usize) {
+        self.state
+            .lock()
+            .expect("throttle state")
+            .waiting
+            .remove(&id);
+    }
+
+    pub fn stream<S>(&self, inner: S) -> Throttled<S>
+    where
+        S: Stream<Error = Error>,
+        S::Item: AsRef<[u8]>,
+    {
+        let id = {
+            let mut state = self.state.lock().expect("throttle state");
+            state.ids += 1;
+            state.ids
+        };
+        Throttled {
+            throttle: self.clone(),
+            id,
+            inner,
+            pending: None,
+        }
+    }
+}
+
+pub(crate) struct Throttled<S: Stream> {
+    throttle: Throttle,
+    id: usize,
+    inner: S,
+    /// The delayed chunk with the generation of the rate it was reserved with.
+    pending: Option<(Delay, S::Item, u64)>,
+}
+
+impl<S> Throttled<S>
+where
+    S: Stream<Error = Error>,
+    S::Item: AsRef<[u8]>,
+{
+    /// Delays `chunk` until its reserved deadline. Returns the chunk if it can be delivered now.
+    fn delay(&mut self, chunk: S::Item) -> Option<S::Item> {
+        match self.throttle.reserve(chunk.as_ref().len()) {
+            (generation, Some(deadline)) if deadline > Instant::now() => {
+                self.pending = Some((Delay::new(deadline), chunk, generation));
+                None
+            }
+            _ => Some(chunk),
+        }
+    }
+}
+
+impl<S> Stream for Throttled<S>
+where
+    S: Stream<Error = Error>,
+    S::Item: AsRef<[u8]>,
+{
+    type Item = S::Item;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        loop {
+            if let Some((mut delay, chunk, generation)) = self.pending.take() {
+                let ready = delay
+                    .poll()
+                    .map_err(|e| error::from(io::Error::other(e)))?
+                    .is_ready();
+                if ready {
+                    self.throttle.unpark(self.id);
+                    return Ok(Async::Ready(Some(chunk)));
+                }
+                if self.throttle.park(self.id, generation) {
+                    self.pending = Some((delay, chunk, generation));
+                    return Ok(Async::NotReady);
+                }
+                // The rate changed while the chunk was delayed.
+                match self.delay(chunk) {
+                    Some(chunk) => return Ok(Async::Ready(Some(chunk))),
+                    None => continue,
+                }
+            }
+            let chunk = match futures::try_ready!(self.inner.poll()) {
+                Some(chunk) => chunk,
+                None => return Ok(Async::Ready(None)),
+            };
+            if let Some(chunk) = self.delay(chunk) {
+                return Ok(Async::Ready(Some(chunk)));
+            }
+        }
+    }
+}
+
+impl<S: Stream> Drop for Throttled<S> {
+    fn drop(&mut self) {
+        self.throttle.unpark(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve() {
+        let throttle = Throttle::new(None);
+        assert_eq!(throttle.reserve(1024), (0, None));
+
+        throttle.set_rate(Some(1024));
+        let now = Instant::now();
+        let (generation, first) = throttle.reserve(512);
+        let second = throttle.reserve(512).1.unwrap();
+        assert_eq!(generation, 1);
+        assert!(first.unwrap() >= now + Duration::from_millis(500));
+        assert!(second >= now + Duration::from_secs(1));
+
+        // A clone shares the same budget.
+        let clone = throttle.clone();
+        let third = clone.reserve(1024).1.unwrap();
+        assert!(third >= now + Duration::from_secs(2));
+
+        throttle.set_rate(Some(0));
+        assert_eq!(clone.rate(), None);
+    }
+
+    #[test]
+    fn set_rate_while_pending() {
+        use std::thread;
+        use tokio::runtime::Runtime;
+
+        let mut rt = Runtime::new().unwrap();
+        let chunks = || futures::stream::iter_ok::<_, Error>(vec![vec![0u8; 1024], vec![0; 1024]]);
+
+        // At 1 byte per second the first chunk is delayed for 17 minutes.
+        let throttle = Throttle::new(Some(1));
+        let stream = throttle.stream(chunks());
+        let clone = throttle.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            clone.set_rate(None);
+        });
+        let start = Instant::now();
+        let received = rt.block_on(stream.collect()).unwrap();
+        assert_eq!(received.len(), 2);
+        assert!(start.elapsed() < Duration::from_secs(10));
+
+        // A higher rate reserves the delayed chunk again.
+        let throttle = Throttle::new(Some(1));
+        let stream = throttle.stream(chunks());
+        let clone = throttle.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            clone.set_rate(Some(1024 * 1024));
+        });
+        let start = Instant::now();
+        assert_eq!(rt.block_on(stream.collect()).unwrap().len(), 2);
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(throttle.state.lock().unwrap().waiting.is_empty());
+    }
+}
@@ -110,6 +110,15 @@ macro_rules! bitflags_serde {
             }
         }
 
+        impl Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_u64(u64::from(self.bits))
+            }
+        }
+
         impl fmt::Display for $name {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 self.bits.fmt(f)
@@ -120,7 +129,8 @@ macro_rules! bitflags_serde {
 // }}}
 
 /// See the [Message Object](https://docs.mod.io/#message-object) docs for more information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[allow(dead_code)]
 pub struct ModioMessage {
     pub code: u16,
     pub message: String,
@@ -131,12 +141,15 @@ pub struct ModioMessage {
 pub type ModioResult<T> = EntityResult<T>;
 
 /// Result type for editing games, mods and files.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum EntityResult<T> {
     Entity(T),
     /// The request was successful however no new data was submitted.
-    #[serde(deserialize_with = "deserialize_message")]
+    #[serde(
+        deserialize_with = "deserialize_message",
+        serialize_with = "serialize_message"
+    )]
     NoChanges,
 }
 
@@ -147,9 +160,21 @@ where
     ModioMessage::deserialize(deserializer).map(|_| ())
 }
 
+/// Serialize `EntityResult::NoChanges` as the message returned by mod.io.
+fn serialize_message<S>(serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    ModioMessage {
+        code: 204,
+        message: "No changes were made.".to_string(),
+    }
+    .serialize(serializer)
+}
+
 /// See the [Multiple Item Response](https://docs.mod.io/#response-formats) docs for more
 /// information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct List<T> {
     pub data: Vec<T>,
     #[serde(rename = "result_count")]
@@ -164,7 +189,7 @@ pub struct List<T> {
 
 impl<T> List<T> {
     pub fn first(&self) -> Option<&T> {
-        self.data.get(0)
+        self.data.first()
     }
 
     pub fn shift(&mut self) -> Option<T> {
@@ -203,14 +228,14 @@ impl<'a, T> IntoIterator for &'a List<T> {
 }
 
 /// See the [Error Object](https://docs.mod.io/#error-object) docs for more information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ModioErrorResponse {
     #[serde(rename = "error")]
     pub error: ClientError,
 }
 
 /// See the [Error Object](https://docs.mod.io/#error-object) docs for more information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ClientError {
     pub code: u16,
     pub message: String,
@@ -218,7 +243,7 @@ pub struct ClientError {
 }
 
 /// See the [User Object](https://docs.mod.io/#user-object) docs for more information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct User {
     pub id: u32,
     pub name_id: String,
@@ -232,7 +257,7 @@ pub struct User {
 }
 
 /// See the [Avatar Object](https://docs.mod.io/#avatar-object) docs for more information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Avatar {
     pub filename: String,
     pub original: Url,
@@ -241,7 +266,7 @@ pub struct Avatar {
 }
 
 /// See the [Logo Object](https://docs.mod.io/#logo-object) docs for more information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Logo {
     pub filename: String,
     pub original: Url,
@@ -262,7 +287,7 @@ enum_number! {
 }
 
 /// See the [User Event Object](https://docs.mod.io/#user-event-object) docs for more information.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Event {
     pub id: u32,
     pub game_id: u32,
@@ -272,9 +297,21 @@ pub struct Event {
     pub event_type: EventType,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum EventType {
+    /// Primary file of a mod changed, the mod should be updated.
+    ModfileChanged,
+    /// Mod is marked as accepted and public.
+    ModAvailable,
+    /// Mod is marked as not accepted, deleted or hidden.
+    ModUnavailable,
+    /// Mod has been updated.
+    ModEdited,
+    /// Mod has been permanently deleted.
+    ModDeleted,
+    /// User has joined or left the mod team.
+    ModTeamChanged,
     /// User has joined a team.
     UserTeamJoin,
     /// User has left a team.
@@ -288,6 +325,12 @@ pub enum EventType {
 impl fmt::Display for EventType {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
+            EventType::ModfileChanged => "MODFILE_CHANGED",
+            EventType::ModAvailable => "MOD_AVAILABLE",
+            EventType::ModUnavailable => "MOD_UNAVAILABLE",
+            EventType::ModEdited => "MOD_EDITED",
+            EventType::ModDeleted => "MOD_DELETED",
+            EventType::ModTeamChanged => "MOD_TEAM_CHANGED",
             EventType::UserTeamJoin => "USER_TEAM_JOIN",
             EventType::UserTeamLeave => "USER_TEAM_LEAVE",
             EventType::UserSubscribe => "USER_SUBSCRIBE",
@@ -325,7 +368,7 @@ pub mod game {
     use std::fmt;
 
     /// See the [Game Object](https://docs.mod.io/#game-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Game {
         pub id: u32,
         pub status: Status,
@@ -440,7 +483,7 @@ pub mod game {
     bitflags_serde!(ApiAccessOptions, u8);
 
     /// See the [Icon Object](https://docs.mod.io/#icon-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Icon {
         pub filename: String,
         pub original: Url,
@@ -451,7 +494,7 @@ pub mod game {
 
     /// See the [Header Image Object](https://docs.mod.io/#header-image-object) docs for more
     /// information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct HeaderImage {
         pub filename: String,
         pub original: Url,
@@ -459,7 +502,7 @@ pub mod game {
 
     /// See the [Game Tag Option Object](https://docs.mod.io/#game-tag-option-object) docs for more
     /// information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct TagOption {
         pub name: String,
         #[serde(rename = "type")]
@@ -468,7 +511,7 @@ pub mod game {
         pub tags: Vec<String>,
     }
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     #[serde(rename_all = "lowercase")]
     pub enum TagType {
         Checkboxes,
@@ -490,7 +533,7 @@ pub mod mods {
     use serde::de::{Deserialize, Deserializer};
 
     /// See the [Mod Object](https://docs.mod.io/#mod-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Mod {
         pub id: u32,
         pub game_id: u32,
@@ -513,7 +556,11 @@ pub mod mods {
         #[serde(deserialize_with = "deserialize_modfile")]
         pub modfile: Option<File>,
         pub media: Media,
-        #[serde(rename = "metadata_kvp", deserialize_with = "deserialize_kvp")]
+        #[serde(
+            rename = "metadata_kvp",
+            deserialize_with = "deserialize_kvp",
+            serialize_with = "serialize_kvp"
+        )]
         pub metadata: MetadataMap,
         pub tags: Vec<Tag>,
         pub stats: Statistics,
@@ -543,7 +590,7 @@ pub mod mods {
     bitflags_serde!(MaturityOption, u8);
 
     /// See the [Mod Event Object](https://docs.mod.io/#mod-event-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Event {
         pub id: u32,
         pub mod_id: u32,
@@ -552,7 +599,7 @@ pub mod mods {
         pub event_type: EventType,
     }
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
     pub enum EventType {
         /// Primary file changed, the mod should be updated.
@@ -597,7 +644,7 @@ pub mod mods {
 
     /// See the [Mod Dependency Object](https://docs.mod.io/#mod-dependencies-object) docs for more
     /// information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Dependency {
         pub mod_id: u32,
         pub date_added: u64,
@@ -605,7 +652,7 @@ pub mod mods {
 
     /// See the [Mod Media Object](https://docs.mod.io/#mod-media-object) docs for more
     /// information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Media {
         #[serde(default = "Vec::new")]
         pub youtube: Vec<String>,
@@ -616,7 +663,7 @@ pub mod mods {
     }
 
     /// See the [Image Object](https://docs.mod.io/#image-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Image {
         pub filename: String,
         pub original: Url,
@@ -625,7 +672,7 @@ pub mod mods {
 
     /// See the [Statistics Object](https://docs.mod.io/#stats-object) docs for more
     /// information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Statistics {
         pub mod_id: u32,
         pub downloads_total: u32,
@@ -637,7 +684,7 @@ pub mod mods {
         pub date_expires: u64,
     }
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Popularity {
         #[serde(rename = "popularity_rank_position")]
         pub rank_position: u32,
@@ -645,7 +692,7 @@ pub mod mods {
         pub rank_total: u32,
     }
 
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Ratings {
         #[serde(rename = "ratings_total")]
         pub total: u32,
@@ -676,6 +723,41 @@ pub mod mods {
         },
     }
 
+    impl Serialize for Rating {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            #[derive(Serialize)]
+            struct R {
+                game_id: u32,
+                mod_id: u32,
+                rating: i8,
+                date_added: u64,
+            }
+
+            let (game_id, mod_id, date_added, rating) = match *self {
+                Rating::Positive {
+                    game_id,
+                    mod_id,
+                    date_added,
+                } => (game_id, mod_id, date_added, 1),
+                Rating::Negative {
+                    game_id,
+                    mod_id,
+                    date_added,
+                } => (game_id, mod_id, date_added, -1),
+            };
+            R {
+                game_id,
+                mod_id,
+                rating,
+                date_added,
+            }
+            .serialize(serializer)
+        }
+    }
+
     impl<'de> Deserialize<'de> for Rating {
         fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
         where
@@ -722,7 +804,7 @@ pub mod mods {
     }
 
     /// See the [Mod Tag Object](https://docs.mod.io/#mod-tag-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Tag {
         pub name: String,
         pub date_added: u64,
@@ -780,7 +862,7 @@ pub mod mods {
                 let mut map = MetadataMap::new();
                 while let Ok(Some(elem)) = seq.next_element::<KV>() {
                     map.entry(elem.metakey)
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(elem.metavalue);
                 }
                 Ok(map)
@@ -789,8 +871,32 @@ pub mod mods {
         deserializer.deserialize_seq(MetadataVisitor)
     }
 
+    /// Serialize a `MetadataMap` as a sequence of key-value objects, the inverse of
+    /// `deserialize_kvp`.
+    fn serialize_kvp<S>(map: &MetadataMap, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        #[derive(Serialize)]
+        struct KV<'a> {
+            metakey: &'a str,
+            metavalue: &'a str,
+        }
+
+        let len = map.values().map(Vec::len).sum();
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        for (metakey, values) in map {
+            for metavalue in values {
+                seq.serialize_element(&KV { metakey, metavalue })?;
+            }
+        }
+        seq.end()
+    }
+
     /// See the [Comment Object](https://docs.mod.io/#comment-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct Comment {
         pub id: u32,
         pub mod_id: u32,
@@ -804,14 +910,14 @@ pub mod mods {
     }
 
     /// See the [Modfile Object](https://docs.mod.io/#modfile-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct File {
         pub id: u32,
         pub mod_id: u32,
         pub date_added: u64,
         pub date_scanned: u64,
-        pub virus_status: u32,
-        pub virus_positive: u32,
+        pub virus_status: VirusStatus,
+        pub virus_positive: VirusPositive,
         pub virustotal_hash: Option<String>,
         pub filesize: u64,
         pub filehash: FileHash,
@@ -822,14 +928,80 @@ pub mod mods {
         pub download: Download,
     }
 
+    enum_number! {
+        /// Current virus scan status of a file.
+        #[derive(Debug)]
+        pub enum VirusStatus {
+            NotScanned = 0,
+            ScanComplete = 1,
+            InProgress = 2,
+            TooLargeToScan = 3,
+            FileNotFound = 4,
+            ErrorScanning = 5,
+        }
+    }
+
+    enum_number! {
+        /// Whether the virus scan detected a threat.
+        #[derive(Debug)]
+        pub enum VirusPositive {
+            NoThreats = 0,
+            Malicious = 1,
+        }
+    }
+
+    impl File {
+        /// Return `true` if the file has been scanned and no threats were detected.
+        pub fn is_safe(&self) -> bool {
+            matches!(self.virus_status, VirusStatus::ScanComplete)
+                && matches!(self.virus_positive, VirusPositive::NoThreats)
+        }
+    }
+
     /// See the [Filehash Object](https://docs.mod.io/#filehash-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct FileHash {
         pub md5: String,
     }
 
+    /// See the [Multipart Upload Object](https://docs.mod.io/#multipart-upload-object) docs for
+    /// more information.
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct MultipartUpload {
+        pub upload_id: String,
+        pub status: MultipartUploadStatus,
+    }
+
+    enum_number! {
+        /// Status of a multipart upload session.
+        #[derive(Debug)]
+        pub enum MultipartUploadStatus {
+            /// Parts can be uploaded.
+            Incomplete = 0,
+            /// The session was completed and waits for processing.
+            Pending = 1,
+            /// The uploaded parts are being merged.
+            Processing = 2,
+            /// The file can be added as modfile.
+            Complete = 3,
+            Cancelled = 4,
+        }
+    }
+
+    /// See the [Multipart Upload Part Object](https://docs.mod.io/#multipart-upload-part-object)
+    /// docs for more information.
+    #[derive(Debug, Deserialize, Serialize)]
+    pub struct MultipartUploadPart {
+        pub upload_id: String,
+        pub part_number: u32,
+        pub part_size: u64,
+        pub chunk_count: u32,
+        pub chunk_size: u64,
+        pub date_added: u64,
+    }
+
     /// See the [Download Object](https://docs.mod.io/#download-object) docs for more information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Clone, Debug, Deserialize, Serialize)]
     pub struct Download {
         pub binary_url: Url,
         pub date_expires: u64,
@@ -837,7 +1009,7 @@ pub mod mods {
 
     /// See the [Team Member Object](https://docs.mod.io/#team-member-object) docs for more
     /// information.
-    #[derive(Debug, Deserialize)]
+    #[derive(Debug, Deserialize, Serialize)]
     pub struct TeamMember {
         pub id: u32,
         pub user: User,
@@ -885,4 +1057,80 @@ pub mod mods {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+
+    use super::game::CommunityOptions;
+    use super::mods::{Mod, Rating};
+    use super::*;
+
+    fn round_trip<T: DeserializeOwned + Serialize>(value: Value) -> Value {
+        let first = serde_json::to_value(serde_json::from_value::<T>(value).unwrap()).unwrap();
+        let second = serde_json::to_value(serde_json::from_value::<T>(first.clone()).unwrap());
+        assert_eq!(first, second.unwrap());
+        first
+    }
+
+    #[test]
+    fn serialize_mod() {
+        let logo = json!({
+            "filename": "logo.png",
+            "original": "https://example.com/logo.png",
+            "thumb_320x180": "https://example.com/320.png",
+            "thumb_640x360": "https://example.com/640.png",
+            "thumb_1280x720": "https://example.com/1280.png",
+        });
+        let value = json!({
+            "id": 2, "game_id": 1, "status": 1, "visible": 1, "maturity_option": 3,
+            "submitted_by": {
+                "id": 3, "name_id": "foo", "username": "Foo", "date_online": 10, "avatar": {},
+                "timezone": "", "language": "", "profile_url": "https://mod.io/members/foo",
+            },
+            "date_added": 1, "date_updated": 2, "date_live": 3, "logo": logo,
+            "homepage_url": null, "name": "Castle", "name_id": "castle", "summary": "",
+            "description": null, "description_plaintext": null, "metadata_blob": null,
+            "profile_url": "https://mod.io/g/foo/m/castle", "modfile": {}, "media": {},
+            "metadata_kvp": [
+                {"metakey": "difficulty", "metavalue": "hard"},
+                {"metakey": "difficulty", "metavalue": "easy"},
+            ],
+            "tags": [{"name": "Map", "date_added": 1}],
+            "stats": {
+                "mod_id": 2, "downloads_total": 10, "subscribers_total": 5,
+                "popularity_rank_position": 1, "popularity_rank_total_mods": 7,
+                "ratings_total": 2, "ratings_positive": 1, "ratings_negative": 1,
+                "ratings_percentage_positive": 50, "ratings_weighted_aggregate": 0.5,
+                "ratings_display_text": "Mixed", "date_expires": 100,
+            },
+        });
+        let value = round_trip::<Mod>(value);
+        assert_eq!(value["modfile"], Value::Null);
+        assert_eq!(value["submitted_by"]["avatar"], Value::Null);
+        assert_eq!(value["maturity_option"], 3);
+        assert_eq!(value["metadata_kvp"][1]["metavalue"], "easy");
+        assert_eq!(value["stats"]["popularity_rank_position"], 1);
+    }
+
+    #[test]
+    fn serialize_custom() {
+        let rating = json!({"game_id": 1, "mod_id": 2, "rating": -1, "date_added": 3});
+        assert_eq!(round_trip::<Rating>(rating.clone()), rating);
+
+        let flags = serde_json::to_value(CommunityOptions::ALL).unwrap();
+        assert_eq!(flags, 3);
+        assert_eq!(round_trip::<CommunityOptions>(flags), 3);
+
+        let message = json!({"code": 204, "message": "No changes were made."});
+        assert_eq!(round_trip::<EntityResult<Rating>>(message.clone()), message);
+
+        let list = json!({
+            "data": [rating],
+            "result_count": 1, "result_total": 1, "result_limit": 100, "result_offset": 0,
+        });
+        assert_eq!(round_trip::<List<Rating>>(list.clone()), list);
+    }
+}
+
 // vim: fdm=marker
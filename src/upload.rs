@@ -0,0 +1,413 @@
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::future::{self, Either, Loop};
+use futures::{Future as StdFuture, Stream as StdStream};
+use log::{debug, warn};
+use tokio_timer::Delay;
+use url::form_urlencoded;
+
+use crate::blocking;
+use crate::error::{self, Error, ErrorKind, Result};
+use crate::multipart::{OnProgress, Progress};
+use crate::types::mods::{MultipartUpload, MultipartUploadPart, MultipartUploadStatus};
+use crate::{Future, Modio, RequestBody, Stream};
+
+/// Size of the parts of a multipart upload. Only the last part may be smaller.
+pub const CHUNK_SIZE: u64 = 50 * 1024 * 1024;
+
+const DEFAULT_RETRIES: u32 = 3;
+
+/// Multipart upload session of a modfile.
+///
+/// Large files are uploaded in parts of [`CHUNK_SIZE`] bytes. Parts that failed to upload are
+/// retried and an interrupted upload can be resumed later with the id of the session, only the
+/// parts not confirmed by mod.io are uploaded again.
+///
+/// Once all parts are uploaded and the session is completed, the modfile is added with
+/// [`AddFileOptions::with_upload_session`](struct.AddFileOptions.html#method.with_upload_session).
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use modio::files::AddFileOptions;
+/// use modio::{Credentials, Error, Modio};
+/// use tokio::runtime::Runtime;
+///
+/// fn main() -> Result<(), Error> {
+///     let mut rt = Runtime::new().expect("new rt");
+///     let modio = Modio::new(Credentials::Token(String::from("user-access-token")))?;
+///     let files = modio.mod_(5, 19).files();
+///
+///     let session = rt.block_on(files.create_upload_session("mod.zip"))?;
+///     rt.block_on(session.upload("mod.zip"))?;
+///     rt.block_on(session.wait(Duration::from_secs(5)))?;
+///
+///     let options = AddFileOptions::with_upload_session(session.id()).version("1.0");
+///     rt.block_on(files.add(options))?;
+///     Ok(())
+/// }
+/// ```
+///
+/// [`CHUNK_SIZE`]: constant.CHUNK_SIZE.html
+#[derive(Clone)]
+pub struct UploadSession {
+    modio: Modio,
+    game: u32,
+    mod_id: u32,
+    id: String,
+    retries: u32,
+    progress: Option<Arc<OnProgress>>,
+}
+
+impl UploadSession {
+    pub(crate) fn new(modio: Modio, game: u32, mod_id: u32, id: String) -> Self {
+        Self {
+            modio,
+            game,
+            mod_id,
+            id,
+            retries: DEFAULT_RETRIES,
+            progress: None,
+        }
+    }
+
+    fn path(&self, more: &str) -> String {
+        let query = form_urlencoded::Serializer::new(String::new())
+            .append_pair("upload_id", &self.id)
+            .finish();
+        format!(
+            "/games/{}/mods/{}/files/multipart{}?{}",
+            self.game, self.mod_id, more, query
+        )
+    }
+
+    /// Return the id of the upload session.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Set how many times a failed part is uploaded again. Defaults to `3`.
+    ///
+    /// Only network errors, server errors and rate limits are retried.
+    pub fn retries(self, retries: u32) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Report the upload progress to the callback after every uploaded part.
+    pub fn progress<F>(self, f: F) -> Self
+    where
+        F: Fn(Progress<'_>) + Send + Sync + 'static,
+    {
+        Self {
+            progress: Some(Arc::new(f)),
+            ..self
+        }
+    }
+
+    /// Provides a stream over the parts that were uploaded successfully. [required: token]
+    pub fn parts(&self) -> Stream<MultipartUploadPart> {
+        token_required!(s self.modio);
+        self.modio.stream(&self.path(""))
+    }
+
+    /// Upload a single part of a file with `total` bytes. [required: token]
+    ///
+    /// `offset` must be a multiple of [`CHUNK_SIZE`](constant.CHUNK_SIZE.html) and `data` must
+    /// contain `CHUNK_SIZE` bytes unless it's the last part of the file.
+    pub fn upload_part(&self, offset: u64, total: u64, data: Bytes) -> Future<MultipartUploadPart> {
+        token_required!(self.modio);
+        let range = content_range(offset, data.len() as u64, total);
+        self.modio
+            .put(&self.path(""), RequestBody::Chunk(data, range))
+    }
+
+    /// Upload a file and complete the session. [required: token]
+    ///
+    /// Parts already confirmed by mod.io are skipped, so an interrupted upload can be resumed
+    /// by calling this method again with the same file.
+    pub fn upload<P: AsRef<Path>>(&self, file: P) -> Future<MultipartUpload> {
+        token_required!(self.modio);
+        let path = file.as_ref().to_path_buf();
+        let total = match fs::metadata(&path) {
+            Ok(meta) => meta.len(),
+            Err(e) => return future_err!(error::from(e)),
+        };
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let session = self.clone();
+
+        let parts = self.parts().collect();
+        Box::new(parts.and_then(move |parts| {
+            let (pending, sent) = match pending_parts(&session.id, total, &parts) {
+                Ok(pending) => pending,
+                Err(e) => return Either::A(future::err(e)),
+            };
+            debug!(
+                "upload session {}: {} of {} parts pending",
+                session.id,
+                pending.len(),
+                part_count(total)
+            );
+
+            let upload = session.clone();
+            let task = future::loop_fn((pending.into_iter(), sent), move |(mut pending, sent)| {
+                let (offset, len) = match pending.next() {
+                    Some(part) => part,
+                    None => return Either::A(future::ok(Loop::Break(()))),
+                };
+                let path = path.clone();
+                let data =
+                    blocking::run(move || read_part(&path, offset, len).map_err(error::from));
+                let upload = upload.clone();
+                let progress = upload.progress.clone();
+                let filename = filename.clone();
+                Either::B(data.and_then(move |data| {
+                    upload.upload_part_retry(offset, total, data).map(move |_| {
+                        let sent = sent + len;
+                        if let Some(progress) = progress {
+                            progress(Progress {
+                                filename: &filename,
+                                sent,
+                                total: Some(total),
+                            });
+                        }
+                        Loop::Continue((pending, sent))
+                    })
+                }))
+            });
+            Either::B(task.and_then(move |_| session.complete()))
+        }))
+    }
+
+    fn upload_part_retry(
+        &self,
+        offset: u64,
+        total: u64,
+        data: Bytes,
+    ) -> impl StdFuture<Item = MultipartUploadPart, Error = Error> {
+        let session = self.clone();
+        future::loop_fn(0, move |attempt| {
+            let retries = session.retries;
+            session
+                .upload_part(offset, total, data.clone())
+                .then(move |ret| match ret {
+                    Ok(part) => Either::A(future::ok(Loop::Break(part))),
+                    Err(e) => match retry_after(&e, attempt) {
+                        Some(wait) if attempt < retries => {
+                            warn!(
+                                "upload of part at {} failed, retry in {:?}: {}",
+                                offset, wait, e
+                            );
+                            Either::B(
+                                Delay::new(Instant::now() + wait)
+                                    .map_err(|e| error::from(io::Error::other(e)))
+                                    .map(move |_| Loop::Continue(attempt + 1)),
+                            )
+                        }
+                        _ => Either::A(future::err(e)),
+                    },
+                })
+        })
+    }
+
+    /// Complete the session after all parts were uploaded. [required: token]
+    pub fn complete(&self) -> Future<MultipartUpload> {
+        token_required!(self.modio);
+        self.modio.post(&self.path("/complete"), String::new())
+    }
+
+    /// Cancel the session and discard the uploaded parts. [required: token]
+    pub fn cancel(&self) -> Future<()> {
+        token_required!(self.modio);
+        self.modio.delete(&self.path(""), RequestBody::Empty)
+    }
+
+    /// Return the current status of the session. [required: token]
+    pub fn status(&self) -> Future<MultipartUpload> {
+        token_required!(self.modio);
+        let id = self.id.clone();
+        let session = self.id.clone();
+        Box::new(
+            self.modio
+                .stream::<MultipartUpload>(&self.path("/sessions"))
+                .filter(move |upload| upload.upload_id == id)
+                .into_future()
+                .map_err(|(e, _)| e)
+                .and_then(move |(upload, _)| {
+                    upload.ok_or_else(|| error::upload_session_not_found(session))
+                }),
+        )
+    }
+
+    /// Poll the status of a completed session every `interval` until the uploaded parts are
+    /// merged and the modfile can be added. [required: token]
+    pub fn wait(&self, interval: Duration) -> Future<MultipartUpload> {
+        token_required!(self.modio);
+        let session = self.clone();
+        Box::new(future::loop_fn((), move |_| {
+            let id = session.id.clone();
+            session
+                .status()
+                .and_then(move |upload| match upload.status {
+                    MultipartUploadStatus::Complete => Either::A(future::ok(Loop::Break(upload))),
+                    MultipartUploadStatus::Cancelled => {
+                        Either::A(future::err(error::upload_cancelled(id)))
+                    }
+                    _ => Either::B(
+                        Delay::new(Instant::now() + interval)
+                            .map_err(|e| error::from(io::Error::other(e)))
+                            .map(|_| Loop::Continue(())),
+                    ),
+                })
+        }))
+    }
+}
+
+fn part_count(total: u64) -> u64 {
+    total.div_ceil(CHUNK_SIZE)
+}
+
+/// Return the offset and length of the parts not yet uploaded and the number of bytes already
+/// uploaded.
+fn pending_parts(
+    upload_id: &str,
+    total: u64,
+    parts: &[MultipartUploadPart],
+) -> Result<(Vec<(u64, u64)>, u64)> {
+    let count = part_count(total);
+    let len = |number: u64| CHUNK_SIZE.min(total - (number - 1) * CHUNK_SIZE);
+
+    let mut confirmed = vec![false; count as usize];
+    let mut sent = 0;
+    for part in parts {
+        let number = u64::from(part.part_number);
+        if number == 0 || number > count || part.part_size != len(number) {
+            return Err(error::upload_size_mismatch(upload_id, part.part_number));
+        }
+        if !confirmed[number as usize - 1] {
+            confirmed[number as usize - 1] = true;
+            sent += part.part_size;
+        }
+    }
+    let pending = (1..=count)
+        .filter(|number| !confirmed[*number as usize - 1])
+        .map(|number| ((number - 1) * CHUNK_SIZE, len(number)))
+        .collect();
+    Ok((pending, sent))
+}
+
+fn content_range(offset: u64, len: u64, total: u64) -> String {
+    format!(
+        "bytes {}-{}/{}",
+        offset,
+        (offset + len).saturating_sub(1),
+        total
+    )
+}
+
+fn read_part(path: &Path, offset: u64, len: u64) -> io::Result<Bytes> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = Vec::with_capacity(len as usize);
+    file.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "file changed during upload",
+        ));
+    }
+    Ok(Bytes::from(buf))
+}
+
+/// Return the time to wait before retrying a failed request or `None` if the error is permanent.
+fn retry_after(err: &Error, attempt: u32) -> Option<Duration> {
+    let backoff = Duration::from_secs(1 << attempt.min(6));
+    match err.kind() {
+        ErrorKind::RateLimit { reset } => Some(*reset),
+        ErrorKind::Fault { code, .. } if code.is_server_error() => Some(backoff),
+        ErrorKind::Reqwest(e) if !e.is_client_error() => Some(backoff),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(part_number: u32, part_size: u64) -> MultipartUploadPart {
+        MultipartUploadPart {
+            upload_id: "id".into(),
+            part_number,
+            part_size,
+            chunk_count: 3,
+            chunk_size: CHUNK_SIZE,
+            date_added: 0,
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(content_range(0, 10, 10), "bytes 0-9/10");
+        assert_eq!(
+            content_range(CHUNK_SIZE, 5, CHUNK_SIZE + 5),
+            format!("bytes {}-{}/{}", CHUNK_SIZE, CHUNK_SIZE + 4, CHUNK_SIZE + 5)
+        );
+    }
+
+    #[test]
+    fn resume() {
+        let total = 2 * CHUNK_SIZE + 100;
+
+        let (pending, sent) = pending_parts("id", total, &[]).unwrap();
+        assert_eq!(
+            pending,
+            vec![
+                (0, CHUNK_SIZE),
+                (CHUNK_SIZE, CHUNK_SIZE),
+                (2 * CHUNK_SIZE, 100)
+            ]
+        );
+        assert_eq!(sent, 0);
+
+        let parts = [part(1, CHUNK_SIZE), part(3, 100)];
+        let (pending, sent) = pending_parts("id", total, &parts).unwrap();
+        assert_eq!(pending, vec![(CHUNK_SIZE, CHUNK_SIZE)]);
+        assert_eq!(sent, CHUNK_SIZE + 100);
+
+        let err = pending_parts("id", total, &[part(3, 99)]).unwrap_err();
+        assert!(matches!(err.kind(), ErrorKind::Upload(_)));
+        assert!(pending_parts("id", total, &[part(4, 1)]).is_err());
+    }
+
+    #[test]
+    fn status_pages() {
+        use tokio::runtime::Runtime;
+
+        use crate::auth::Credentials;
+        use crate::tests::serve_pages;
+
+        let page = |id: &str, offset: u32| {
+            serde_json::json!({
+                "data": [{"upload_id": id, "status": 3}],
+                "result_count": 1, "result_total": 2, "result_limit": 1, "result_offset": offset,
+            })
+            .to_string()
+        };
+        let (host, hits) = serve_pages(vec![page("other", 0), page("id", 1)]);
+        let modio = Modio::host(host.as_str(), Credentials::Token("token".into())).unwrap();
+        let session = UploadSession::new(modio, 1, 2, "id".into());
+        let mut rt = Runtime::new().unwrap();
+
+        let upload = rt.block_on(session.status()).unwrap();
+        assert_eq!(upload.upload_id, "id");
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+}
@@ -18,7 +18,7 @@ impl Users {
     /// List all users registered on [mod.io](https:://mod.io).
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn list(&self, filter: &Filter) -> Future<List<User>> {
+    pub fn list(&self, filter: &Filter<impl Scope<endpoint::Users>>) -> Future<List<User>> {
         let mut uri = vec!["/users".into()];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -30,7 +30,7 @@ impl Users {
     /// Provides a stream over all users registered on [mod.io](https:://mod.io).
     ///
     /// See [Filters and sorting](filters/index.html).
-    pub fn iter(&self, filter: &Filter) -> Stream<User> {
+    pub fn iter(&self, filter: &Filter<impl Scope<endpoint::Users>>) -> Stream<User> {
         let mut uri = vec!["/users".into()];
         let query = filter.to_query_string();
         if !query.is_empty() {
@@ -39,6 +39,13 @@ impl Users {
         self.modio.stream(&uri.join("?"))
     }
 
+    /// Provides a stream over all users that pass any of the alternatives.
+    ///
+    /// See [OR-queries](../filter/any/index.html).
+    pub fn iter_any(&self, filter: &AnyOf<impl Scope<endpoint::Users>>) -> Stream<User> {
+        self.modio.stream_any("/users", filter)
+    }
+
     /// Return a user by id
     pub fn get(&self, id: u32) -> Future<User> {
         self.modio.get(&format!("/users/{}", id))
@@ -87,10 +94,23 @@ pub mod filters {
     #[doc(inline)]
     pub use crate::filter::prelude::NameId;
 
-    filter!(DateOnline, DATE_ONLINE, "date_online", Eq, NotEq, In, Cmp, OrderBy);
-    filter!(Username, USERNAME, "username", Eq, NotEq, In, Like, OrderBy);
-    filter!(Timezone, TIMEZONE, "timezone", Eq, NotEq, In, Like);
-    filter!(Language, LANGUAGE, "language", Eq, NotEq, In, Like);
+    type Endpoint = crate::filter::endpoint::Users;
+
+    filter!(DateOnline, DATE_ONLINE, "date_online", crate::filter::Timestamp: Eq, NotEq, In, Cmp, OrderBy);
+    filter!(Username, USERNAME, "username", String: Eq, NotEq, In, Like, OrderBy);
+    filter!(Timezone, TIMEZONE, "timezone", String: Eq, NotEq, In, Like);
+    filter!(Language, LANGUAGE, "language", String: Eq, NotEq, In, Like);
+
+    /// The user fields for [`Filter::from_expr`](crate::filter::Filter::from_expr).
+    pub const SCHEMA: crate::filter::expr::Schema<Endpoint> = crate::filter::expr::Schema::new(&[
+        Fulltext::FIELD,
+        Id::FIELD,
+        NameId::FIELD,
+        DateOnline::FIELD,
+        Username::FIELD,
+        Timezone::FIELD,
+        Language::FIELD,
+    ]);
 }
 
 #[derive(Clone, Copy)]
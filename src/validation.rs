@@ -0,0 +1,500 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use mime::Mime;
+
+use crate::error::{self, Result};
+use crate::games::{TagOption, TagType};
+use crate::multipart::FileSource;
+
+const MESSAGE: &str = "Validation failed. Please see below to fix invalid input.";
+
+const KB: u64 = 1024;
+const MB: u64 = 1024 * KB;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Gif => "gif",
+        }
+    }
+
+    pub fn mime(self) -> Mime {
+        match self {
+            ImageFormat::Png => mime::IMAGE_PNG,
+            ImageFormat::Jpeg => mime::IMAGE_JPEG,
+            ImageFormat::Gif => mime::IMAGE_GIF,
+        }
+    }
+}
+
+/// Constraints for uploaded images.
+pub(crate) struct ImageRules {
+    pub formats: &'static [ImageFormat],
+    pub max_size: u64,
+    pub min_width: u32,
+    pub min_height: u32,
+}
+
+pub(crate) const MOD_LOGO: ImageRules = ImageRules {
+    formats: &[ImageFormat::Png, ImageFormat::Jpeg],
+    max_size: 8 * MB,
+    min_width: 512,
+    min_height: 288,
+};
+
+pub(crate) const MOD_IMAGE: ImageRules = ImageRules {
+    formats: &[ImageFormat::Png, ImageFormat::Jpeg],
+    max_size: 8 * MB,
+    min_width: 0,
+    min_height: 0,
+};
+
+pub(crate) const GAME_LOGO: ImageRules = MOD_LOGO;
+
+pub(crate) const GAME_ICON: ImageRules = ImageRules {
+    formats: &[ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Gif],
+    max_size: MB,
+    min_width: 64,
+    min_height: 64,
+};
+
+pub(crate) const GAME_HEADER: ImageRules = ImageRules {
+    formats: &[ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::Gif],
+    max_size: 256 * KB,
+    min_width: 0,
+    min_height: 0,
+};
+
+/// Maximum size of a modfile uploaded with a single request.
+pub(crate) const MAX_MODFILE_SIZE: u64 = 500 * MB;
+
+/// Maximum size of an images archive.
+pub(crate) const MAX_IMAGES_ZIP_SIZE: u64 = 256 * MB;
+
+/// Collects the invalid fields of request options, keyed by the name of the form field.
+#[derive(Default)]
+pub(crate) struct Validator {
+    errors: HashMap<String, String>,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    pub fn error<F: Into<String>, S: Into<String>>(&mut self, field: F, msg: S) {
+        self.errors
+            .entry(field.into())
+            .or_insert_with(|| msg.into());
+    }
+
+    pub fn required(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.error(field, format!("The {} field is required.", field));
+        }
+    }
+
+    pub fn max_len(&mut self, field: &str, value: Option<&str>, max: usize) {
+        if let Some(value) = value {
+            if value.chars().count() > max {
+                let msg = format!("The {} may not be greater than {} characters.", field, max);
+                self.error(field, msg);
+            }
+        }
+    }
+
+    /// Reject the `reserved` characters, e.g. separators that mod.io can't escape.
+    pub fn reserved(&mut self, field: &str, value: &str, reserved: &str) {
+        if let Some(c) = value.chars().find(|c| reserved.contains(*c)) {
+            self.error(field, format!("The {} may not contain '{}'.", field, c));
+        }
+    }
+
+    /// Check the format, file size and dimensions of an image. Sources without a path are not
+    /// checked.
+    pub fn image(&mut self, field: &str, source: &FileSource, rules: &ImageRules) {
+        let path = match source.path() {
+            Some(path) => path,
+            None => return,
+        };
+        if !self.file_size(field, path, rules.max_size) {
+            return;
+        }
+        match image_info(path) {
+            Ok(Some((format, _, _))) if !rules.formats.contains(&format) => {
+                let formats = rules.formats.iter().map(|f| f.name()).collect::<Vec<_>>();
+                let msg = format!(
+                    "The {} must be a file of type: {}.",
+                    field,
+                    formats.join(", ")
+                );
+                self.error(field, msg);
+            }
+            Ok(Some((_, width, height))) => {
+                if width < rules.min_width || height < rules.min_height {
+                    let msg = format!(
+                        "The {} must be at least {}x{} pixels, got {}x{}.",
+                        field, rules.min_width, rules.min_height, width, height
+                    );
+                    self.error(field, msg);
+                }
+            }
+            Ok(None) => self.error(field, format!("The {} must be an image.", field)),
+            Err(e) => self.error(field, format!("The {} could not be read: {}", field, e)),
+        }
+    }
+
+    /// Check the file size of a zip archive and that it contains at least one entry. Sources
+    /// without a path are not checked.
+    pub fn archive(&mut self, field: &str, source: &FileSource, max_size: u64) {
+        let path = match source.path() {
+            Some(path) => path,
+            None => return,
+        };
+        if !self.file_size(field, path, max_size) {
+            return;
+        }
+        match zip_entries(path) {
+            Ok(Some(0)) => self.error(field, format!("The {} must not be an empty zip.", field)),
+            Ok(Some(_)) => {}
+            Ok(None) => self.error(field, format!("The {} must be a zip archive.", field)),
+            Err(e) => self.error(field, format!("The {} could not be read: {}", field, e)),
+        }
+    }
+
+    /// Returns `false` if the file is missing or too large.
+    fn file_size(&mut self, field: &str, path: &Path, max: u64) -> bool {
+        match fs::metadata(path) {
+            Ok(ref meta) if !meta.is_file() => {
+                self.error(field, format!("The {} must be a file.", field));
+                false
+            }
+            Ok(meta) if meta.len() > max => {
+                let msg = format!(
+                    "The {} may not be greater than {} kilobytes.",
+                    field,
+                    max / KB
+                );
+                self.error(field, msg);
+                false
+            }
+            Ok(meta) if meta.len() == 0 => {
+                self.error(field, format!("The {} must not be empty.", field));
+                false
+            }
+            Ok(_) => true,
+            Err(e) => {
+                let msg = format!("The {} could not be read: {}", field, e);
+                self.error(field, msg);
+                false
+            }
+        }
+    }
+
+    /// Check that the tags are defined by the tag options of the game and that at most one tag
+    /// of a dropdown option is selected.
+    pub fn tags(&mut self, field: &str, tags: &[String], options: &[TagOption]) {
+        let mut invalid = Vec::new();
+        for tag in tags {
+            if !options.iter().any(|o| o.tags.contains(tag)) {
+                invalid.push(tag.as_str());
+            }
+        }
+        if !invalid.is_empty() {
+            let msg = format!(
+                "The selected {} are invalid: {}.",
+                field,
+                invalid.join(", ")
+            );
+            self.error(field, msg);
+            return;
+        }
+        for option in options {
+            if let TagType::Dropdown = option.kind {
+                let selected = tags.iter().filter(|t| option.tags.contains(t)).count();
+                if selected > 1 {
+                    let msg = format!("Only one tag of '{}' can be selected.", option.name);
+                    self.error(field, msg);
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(error::validation(MESSAGE, self.errors))
+        }
+    }
+}
+
+/// Detect the content type of a file from its magic bytes.
+pub(crate) fn content_type(path: &Path) -> Option<Mime> {
+    if let Ok(Some((format, _, _))) = image_info(path) {
+        return Some(format.mime());
+    }
+    let mut magic = [0; 6];
+    let n = read_up_to(&mut fs::File::open(path).ok()?, &mut magic).ok()?;
+    let magic = &magic[..n];
+    let mime = if magic.starts_with(b"PK\x03\x04") || magic.starts_with(b"PK\x05\x06") {
+        "application/zip"
+    } else if magic.starts_with(b"7z\xBC\xAF\x27\x1C") {
+        "application/x-7z-compressed"
+    } else if magic.starts_with(b"Rar!\x1A\x07") {
+        "application/vnd.rar"
+    } else if magic.starts_with(&[0x1F, 0x8B]) {
+        "application/gzip"
+    } else {
+        return None;
+    };
+    mime.parse().ok()
+}
+
+/// Detect the format and dimensions of an image from its header.
+pub(crate) fn image_info(path: &Path) -> io::Result<Option<(ImageFormat, u32, u32)>> {
+    let mut file = fs::File::open(path)?;
+    let mut header = [0; 26];
+    let n = read_up_to(&mut file, &mut header)?;
+    let header = &header[..n];
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") && header.len() >= 24 && &header[12..16] == b"IHDR"
+    {
+        let width = u32::from_be_bytes([header[16], header[17], header[18], header[19]]);
+        let height = u32::from_be_bytes([header[20], header[21], header[22], header[23]]);
+        return Ok(Some((ImageFormat::Png, width, height)));
+    }
+    if (header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a")) && header.len() >= 10 {
+        let width = u16::from_le_bytes([header[6], header[7]]);
+        let height = u16::from_le_bytes([header[8], header[9]]);
+        return Ok(Some((ImageFormat::Gif, width.into(), height.into())));
+    }
+    if header.starts_with(&[0xFF, 0xD8]) {
+        file.seek(SeekFrom::Start(2))?;
+        return jpeg_size(&mut file).map(|size| size.map(|(w, h)| (ImageFormat::Jpeg, w, h)));
+    }
+    Ok(None)
+}
+
+/// Walk the JPEG segments until the start of frame marker containing the dimensions.
+fn jpeg_size<R: Read>(r: &mut R) -> io::Result<Option<(u32, u32)>> {
+    let mut marker = [0; 2];
+    loop {
+        if read_up_to(r, &mut marker)? < 2 || marker[0] != 0xFF {
+            return Ok(None);
+        }
+        // Fill bytes
+        while marker[1] == 0xFF {
+            let mut b = [0; 1];
+            if read_up_to(r, &mut b)? < 1 {
+                return Ok(None);
+            }
+            marker[1] = b[0];
+        }
+        match marker[1] {
+            // Standalone markers without length
+            0x01 | 0xD0..=0xD7 => continue,
+            0xD9 | 0xDA => return Ok(None),
+            _ => {}
+        }
+        let mut len = [0; 2];
+        if read_up_to(r, &mut len)? < 2 {
+            return Ok(None);
+        }
+        let len = u16::from_be_bytes(len);
+        if len < 2 {
+            return Ok(None);
+        }
+        let sof = matches!(marker[1], 0xC0..=0xCF) && !matches!(marker[1], 0xC4 | 0xC8 | 0xCC);
+        if sof {
+            let mut frame = [0; 5];
+            if read_up_to(r, &mut frame)? < 5 {
+                return Ok(None);
+            }
+            let height = u16::from_be_bytes([frame[1], frame[2]]);
+            let width = u16::from_be_bytes([frame[3], frame[4]]);
+            return Ok(Some((width.into(), height.into())));
+        }
+        io::copy(&mut r.take(u64::from(len - 2)), &mut io::sink())?;
+    }
+}
+
+/// Return the number of entries of a zip archive or `None` if the file is not a zip archive.
+pub(crate) fn zip_entries(path: &Path) -> io::Result<Option<u64>> {
+    const EOCD: &[u8] = b"PK\x05\x06";
+    const EOCD_LEN: u64 = 22;
+
+    let mut file = fs::File::open(path)?;
+    let mut magic = [0; 4];
+    let n = read_up_to(&mut file, &mut magic)?;
+    if n < 4 || (magic != *b"PK\x03\x04" && magic != *EOCD) {
+        return Ok(None);
+    }
+    // The end of central directory record is followed by a comment of up to 64 KiB.
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(EOCD_LEN + u64::from(u16::MAX));
+    file.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    let pos = tail
+        .windows(EOCD.len())
+        .rposition(|w| w == EOCD)
+        .filter(|pos| tail.len() - pos >= EOCD_LEN as usize);
+    match pos {
+        Some(pos) => {
+            let entries = u16::from_le_bytes([tail[pos + 10], tail[pos + 11]]);
+            Ok(Some(entries.into()))
+        }
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "invalid zip archive",
+        )),
+    }
+}
+
+fn read_up_to<R: Read>(r: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match r.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(read) => n += read,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn tempfile(name: &str, data: &[u8]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("modio-{}-{}", std::process::id(), name));
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        data.extend_from_slice(&[8, 6, 0, 0, 0]);
+        data
+    }
+
+    #[test]
+    fn images() {
+        let path = tempfile("image.png", &png(640, 360));
+        assert_eq!(
+            image_info(&path).unwrap(),
+            Some((ImageFormat::Png, 640, 360))
+        );
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&[64, 0, 32, 0, 0, 0, 0]);
+        fs::write(&path, gif).unwrap();
+        assert_eq!(image_info(&path).unwrap(), Some((ImageFormat::Gif, 64, 32)));
+
+        // SOI, APP0 segment with 2 bytes of data, SOF0 with 8 bits, height 288, width 512
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, 0xFF, 0xC0, 0x00, 0x11, 0x08, 0x01,
+            0x20, 0x02, 0x00,
+        ];
+        fs::write(&path, jpeg).unwrap();
+        assert_eq!(
+            image_info(&path).unwrap(),
+            Some((ImageFormat::Jpeg, 512, 288))
+        );
+
+        assert_eq!(content_type(&path), Some(mime::IMAGE_JPEG));
+
+        fs::write(&path, b"not an image").unwrap();
+        assert_eq!(image_info(&path).unwrap(), None);
+        assert_eq!(content_type(&path), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty_zip() {
+        let mut eocd = b"PK\x05\x06".to_vec();
+        eocd.extend_from_slice(&[0; 18]);
+        let path = tempfile("empty.zip", &eocd);
+        assert_eq!(zip_entries(&path).unwrap(), Some(0));
+
+        eocd[10] = 3;
+        let mut zip = b"PK\x03\x04".to_vec();
+        zip.extend_from_slice(&eocd);
+        fs::write(&path, zip).unwrap();
+        assert_eq!(zip_entries(&path).unwrap(), Some(3));
+        assert_eq!(content_type(&path).unwrap(), "application/zip");
+
+        fs::write(&path, b"plain text").unwrap();
+        assert_eq!(zip_entries(&path).unwrap(), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn fields() {
+        let path = tempfile("logo.png", &png(100, 100));
+        let logo = FileSource {
+            inner: crate::multipart::FileStream::open(&path),
+            filename: "logo.png".into(),
+            mime: mime::IMAGE_PNG,
+        };
+        let options = [TagOption {
+            name: "Engine".into(),
+            kind: TagType::Dropdown,
+            hidden: false,
+            tags: vec!["Engine A".into(), "Engine B".into()],
+        }];
+
+        let mut v = Validator::new();
+        v.required("name", " ");
+        v.max_len("summary", Some(&"x".repeat(251)), 250);
+        v.image("logo", &logo, &MOD_LOGO);
+        v.tags("tags", &["Engine A".into(), "Engine B".into()], &options);
+        let err = v.finish().unwrap_err();
+        match err.kind() {
+            ErrorKind::Validation(_, errors) => {
+                let mut fields = errors.keys().map(String::as_str).collect::<Vec<_>>();
+                fields.sort_unstable();
+                assert_eq!(fields, ["logo", "name", "summary", "tags"]);
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let mut v = Validator::new();
+        v.tags("tags", &["Engine C".into()], &options);
+        assert!(v.finish().is_err());
+
+        let mut v = Validator::new();
+        v.reserved("metadata", "a:b", ":,");
+        assert!(v.finish().is_err());
+
+        let mut v = Validator::new();
+        v.tags("tags", &["Engine A".into()], &options);
+        v.max_len("summary", Some("short"), 250);
+        v.reserved("metadata", "a*b", ":,");
+        assert!(v.finish().is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+}